@@ -12,7 +12,33 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use abuild::command;
+use abuild::message::{self, Event, Level};
+use std::process::ExitCode;
 
-fn main() -> command::Result<()> {
-    command::parse_args().run()
+fn main() -> ExitCode {
+    let cli = match command::try_parse_args() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return ExitCode::from(if e.use_stderr() {
+                command::EXIT_USAGE
+            } else {
+                0
+            });
+        }
+    };
+    let code = match cli.run() {
+        Ok(code) => code,
+        Err(e) => {
+            message::emit(&Event::Diagnostic {
+                project: None,
+                level: Level::Error,
+                message: e.to_string(),
+                hint: e.hint(),
+            });
+            e.exit_code()
+        }
+    };
+    message::emit(&Event::Exit { code });
+    ExitCode::from(code)
 }
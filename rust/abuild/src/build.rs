@@ -0,0 +1,888 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! building projects with their build tools
+//!
+//! > target:
+//! > > profile:
+//! > > > build:
+//! > > > > project: ... # the build directory of the build tool
+//! > > >
+//! > > > binary ... # the binaries of all projects
+//! > >
+//! >
+//! > > target triple: # `--target <triple>`
+//! > > > profile: ...
+//! > >
+//! >
+//!
+//! + ![note] the binaries of all projects share the profile directory, so a project fails to
+//!   build if its binary would overwrite a binary of another project with the same name.
+//! + ![note] with `--target`, the target is passed to cargo (`--target`, and the linker as
+//!   `CARGO_TARGET_<TRIPLE>_LINKER`), and the compilers of the target are passed to make and
+//!   cmake (see `[target.<triple>]` in the config).
+//!
+//! > dependencies:
+//! >
+//! > a project can depend on other projects of the workspace (`dependencies` in its config),
+//! > which are built before it. the dependencies are passed to the build tool by environment
+//! > variables (`<NAME>` is the dependency name in uppercase, non-alphanumeric characters are
+//! > replaced by `_`):
+//! >
+//! > - `ABUILD_DEPS`: the names of the dependencies, separated by `,`
+//! > - `ABUILD_DEP_<NAME>_PROJECT_DIR`: the project directory of the dependency
+//! > - `ABUILD_DEP_<NAME>_OUT_DIR`: the build directory of the dependency
+//! > - `ABUILD_DEP_<NAME>_ARTIFACTS`: the binaries and libraries of the dependency (a path list)
+//! > - `LIBRARY_PATH`: the directories of the libraries are prepended
+//!
+//! > jobs:
+//! >
+//! > the projects which do not depend on each other are built at the same time (up to `--jobs`
+//! > projects, default: the number of CPUs). the output of each project is printed at once when
+//! > it is finished, so the output of the projects is not interleaved. after the first failure
+//! > no more projects are started, unless `--keep-going` is provided.
+//!
+
+use crate::config::{Profile, ProjectConfig, Target};
+use crate::fingerprint::{FINGERPRINT_DIR_NAME, Fingerprint};
+use crate::lang::{BuildTool, Language};
+use crate::message::{Event, MessageFormat, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::sync::mpsc;
+
+pub const TARGET_DIR_NAME: &str = "target";
+pub const BUILD_DIR_NAME: &str = "build";
+pub const DEPS_DIR_NAME: &str = "deps";
+pub const DEFAULT_PROFILE: &str = "debug";
+
+/// the installed binaries of this process and their projects, see [`Context::install`]
+static INSTALLED: Mutex<BTreeMap<PathBuf, String>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug)]
+pub enum Error {
+    IOError(PathBuf, io::Error),
+    ToolNotFoundError(String),
+    FailedError {
+        project: String,
+        tool: BuildTool,
+        code: Option<i32>,
+    },
+    BinaryNotFoundError {
+        project: String,
+        binary: String,
+    },
+    UnknownDependencyError {
+        project: String,
+        dependency: String,
+    },
+    DependencyCycleError(Vec<String>),
+    TargetNotInstalledError {
+        target: String,
+        tool: String,
+    },
+    UnsupportedBuildToolError(BuildTool),
+    BinaryConflictError {
+        binary: String,
+        project: String,
+        other: String,
+    },
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::IOError(path, e) => write!(f, "IO error: {}: {}", path.display(), e),
+            Error::ToolNotFoundError(tool) => write!(f, "The build tool '{}' was not found", tool),
+            Error::FailedError {
+                project,
+                tool,
+                code: Some(code),
+            } => write!(
+                f,
+                "Failed to build the project '{}': {} exited with code '{}'",
+                project, tool, code
+            ),
+            Error::FailedError {
+                project,
+                tool,
+                code: None,
+            } => write!(
+                f,
+                "Failed to build the project '{}': {} was terminated",
+                project, tool
+            ),
+            Error::BinaryNotFoundError { project, binary } => write!(
+                f,
+                "The binary '{}' was not found in the project '{}'",
+                binary, project
+            ),
+            Error::UnknownDependencyError {
+                project,
+                dependency,
+            } => write!(
+                f,
+                "The dependency '{}' of the project '{}' is not a project of the workspace",
+                dependency, project
+            ),
+            Error::DependencyCycleError(projects) => write!(
+                f,
+                "The projects depend on each other in a cycle: {}",
+                projects.join(" -> ")
+            ),
+            Error::TargetNotInstalledError { target, tool } => write!(
+                f,
+                "No toolchain for the target '{}' was found: {} is not installed",
+                target, tool
+            ),
+            Error::UnsupportedBuildToolError(tool) => {
+                write!(f, "The build tool '{}' is not supported yet", tool)
+            }
+            Error::BinaryConflictError {
+                binary,
+                project,
+                other,
+            } => write!(
+                f,
+                "The binary '{}' of the project '{}' would overwrite the binary of the project \
+                 '{}'",
+                binary, project, other
+            ),
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::IOError(_, e) => Some(e),
+            Error::ToolNotFoundError(_)
+            | Error::FailedError { .. }
+            | Error::BinaryNotFoundError { .. }
+            | Error::UnknownDependencyError { .. }
+            | Error::DependencyCycleError(_)
+            | Error::TargetNotInstalledError { .. }
+            | Error::UnsupportedBuildToolError(_)
+            | Error::BinaryConflictError { .. } => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// a project of a workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub name: String,
+    pub dir: PathBuf,
+    pub config: ProjectConfig,
+}
+
+/// the project and profile to build
+#[derive(Debug, Clone, Copy)]
+pub struct Context<'a> {
+    pub workspace_dir: &'a Path,
+    pub project: &'a Project,
+    pub profile: &'a str,
+    /// the resolved profile
+    pub settings: &'a Profile,
+    /// the target to build for (default: the host)
+    pub target: Option<&'a Target>,
+    /// the built dependencies of the project
+    pub dependencies: &'a [Dependency],
+    /// the output of the build
+    pub output: &'a Output,
+}
+
+/// the output of a job, which is printed at once when the job is finished (if buffered)
+#[derive(Debug, Default)]
+pub struct Output {
+    /// the project of the job
+    project: String,
+    buffer: Option<Mutex<Vec<Event>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Line {
+    Stdout(String),
+    Stderr(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+    Binary,
+    Library,
+}
+
+/// a binary or library built by a project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: ArtifactKind,
+}
+
+/// a built dependency of a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: String,
+    pub project_dir: PathBuf,
+    pub out_dir: PathBuf,
+    pub artifacts: Vec<Artifact>,
+}
+
+/// a build tool which can build projects
+pub trait Backend {
+    /// build the project (or only the binary `binary`), and return the built binaries, which
+    /// were copied into the profile directory, and the built libraries
+    fn build(&self, context: &Context, binary: Option<&str>) -> Result<Vec<Artifact>>;
+    /// the versions of the build tool and compilers, which are part of the fingerprint
+    fn toolchain(&self, context: &Context) -> Result<Vec<String>>;
+    /// the files out of the project directory which were read by the last build (e.g. cargo
+    /// path dependencies), which are fingerprinted too
+    fn inputs(&self, _context: &Context) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+impl Project {
+    /// load the project `name` of the workspace
+    pub fn load(workspace_dir: &Path, name: &str) -> crate::config::Result<Self> {
+        let dir = workspace_dir.join(name);
+        Ok(Self {
+            name: name.to_owned(),
+            config: ProjectConfig::load(&dir)?,
+            dir,
+        })
+    }
+}
+
+impl Context<'_> {
+    /// the profile directory (`target/<profile>`, or `target/<triple>/<profile>`)
+    pub fn profile_dir(&self) -> PathBuf {
+        profile_dir(
+            self.workspace_dir,
+            self.target.map(|target| target.triple.as_str()),
+            self.profile,
+        )
+    }
+    /// the build directory of the project (`target/<profile>/build/<project>`)
+    pub fn build_dir(&self) -> PathBuf {
+        self.profile_dir()
+            .join(BUILD_DIR_NAME)
+            .join(&self.project.name)
+    }
+    /// copy the binary `path` into the profile directory, unless it would overwrite a binary of
+    /// another project
+    pub fn install(&self, path: &Path) -> Result<Artifact> {
+        let profile_dir = self.profile_dir();
+        std::fs::create_dir_all(&profile_dir)
+            .map_err(|e| Error::IOError(profile_dir.clone(), e))?;
+        let file_name = path.file_name().unwrap_or_default();
+        let installed = profile_dir.join(file_name);
+        let name = Path::new(file_name)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        // the binaries installed by this process, the projects may be built at the same time
+        let mut owners = INSTALLED.lock().unwrap_or_else(|e| e.into_inner());
+        let owner = match owners.get(&installed) {
+            Some(owner) => Some(owner.clone()),
+            None => self.owner(&installed),
+        };
+        if let Some(other) = owner
+            && other != self.project.name
+        {
+            return Err(Error::BinaryConflictError {
+                binary: name,
+                project: self.project.name.clone(),
+                other,
+            });
+        }
+        std::fs::copy(path, &installed).map_err(|e| Error::IOError(path.to_path_buf(), e))?;
+        owners.insert(installed.clone(), self.project.name.clone());
+        Ok(Artifact {
+            name,
+            path: installed,
+            kind: ArtifactKind::Binary,
+        })
+    }
+    /// the project whose fingerprint has the binary `installed`, if it still exists
+    fn owner(&self, installed: &Path) -> Option<String> {
+        if !installed.exists() {
+            return None;
+        }
+        let fingerprint_dir = self.profile_dir().join(FINGERPRINT_DIR_NAME);
+        crate::fs::sorted_entries(&fingerprint_dir)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .find_map(|path| {
+                let fingerprint = Fingerprint::load(&path)?;
+                let project = path.file_stem()?.to_string_lossy().into_owned();
+                fingerprint
+                    .artifacts
+                    .iter()
+                    .any(|artifact| {
+                        artifact.kind == ArtifactKind::Binary && artifact.path == installed
+                    })
+                    .then_some(project)
+            })
+    }
+    /// the library `path` in the build directory
+    pub fn library(&self, path: &Path) -> Artifact {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        Artifact {
+            name: stem.strip_prefix("lib").unwrap_or(&stem).to_owned(),
+            path: path.to_path_buf(),
+            kind: ArtifactKind::Library,
+        }
+    }
+    /// the environment variable `name` of the build tools, which is set by the `env` of the
+    /// profile or inherited from abuild
+    pub fn env(&self, name: &str) -> Option<String> {
+        match self.settings.env.get(name) {
+            Some(value) => Some(value.clone()),
+            None => std::env::var(name).ok(),
+        }
+    }
+    /// the command to run the build tool `program`, with the environment variables of the
+    /// profile and the dependencies
+    pub fn command(&self, program: &str) -> Command {
+        let mut command = Command::new(program);
+        command.envs(&self.settings.env);
+        if !self.dependencies.is_empty() {
+            let names = self
+                .dependencies
+                .iter()
+                .map(|dependency| dependency.name.as_str())
+                .collect::<Vec<_>>();
+            command.env("ABUILD_DEPS", names.join(","));
+            let mut library_dirs = Vec::new();
+            for dependency in self.dependencies {
+                let prefix = format!("ABUILD_DEP_{}", env_name(&dependency.name));
+                command
+                    .env(format!("{}_PROJECT_DIR", prefix), &dependency.project_dir)
+                    .env(format!("{}_OUT_DIR", prefix), &dependency.out_dir);
+                if let Ok(artifacts) =
+                    std::env::join_paths(dependency.artifacts.iter().map(|artifact| &artifact.path))
+                {
+                    command.env(format!("{}_ARTIFACTS", prefix), artifacts);
+                }
+                library_dirs.extend(
+                    dependency
+                        .artifacts
+                        .iter()
+                        .filter(|artifact| artifact.kind == ArtifactKind::Library)
+                        .filter_map(|artifact| artifact.path.parent().map(Path::to_path_buf)),
+                );
+            }
+            // the dependencies may share directories, which are not always adjacent
+            let mut seen = BTreeSet::new();
+            library_dirs.retain(|dir| seen.insert(dir.clone()));
+            if !library_dirs.is_empty() {
+                if let Some(library_path) = std::env::var_os("LIBRARY_PATH") {
+                    library_dirs.extend(std::env::split_paths(&library_path));
+                }
+                if let Ok(library_path) = std::env::join_paths(library_dirs) {
+                    command.env("LIBRARY_PATH", library_path);
+                }
+            }
+        }
+        command
+    }
+}
+
+impl Output {
+    /// the output of `project` which is printed immediately
+    pub fn direct(project: &str) -> Self {
+        Self {
+            project: project.to_owned(),
+            buffer: None,
+        }
+    }
+    /// the output of `project` which is printed by `flush`
+    pub fn buffered(project: &str) -> Self {
+        Self {
+            project: project.to_owned(),
+            buffer: Some(Mutex::new(Vec::new())),
+        }
+    }
+    pub fn is_buffered(&self) -> bool {
+        self.buffer.is_some()
+    }
+    /// print a line of the build tool
+    pub fn line(&self, stream: Stream, line: impl Display) {
+        self.emit(Event::Output {
+            project: Some(self.project.clone()),
+            binary: None,
+            stream,
+            line: line.to_string(),
+        });
+    }
+    /// print a message about the project
+    pub fn message(&self, message: impl Display) {
+        self.emit(Event::Message {
+            project: Some(self.project.clone()),
+            message: message.to_string(),
+        });
+    }
+    /// print the event
+    pub fn emit(&self, event: Event) {
+        match &self.buffer {
+            Some(buffer) => buffer.lock().unwrap_or_else(|e| e.into_inner()).push(event),
+            None => crate::message::emit(&event),
+        }
+    }
+    /// print the buffered output
+    pub fn flush(&self) {
+        if let Some(buffer) = &self.buffer {
+            let events = std::mem::take(&mut *buffer.lock().unwrap_or_else(|e| e.into_inner()));
+            let _stdout = io::stdout().lock();
+            let _stderr = io::stderr().lock();
+            events.iter().for_each(crate::message::emit);
+        }
+    }
+}
+
+/// the projects which were built, failed or skipped by `schedule`
+#[derive(Debug)]
+pub struct Schedule<T, E> {
+    pub finished: BTreeMap<String, T>,
+    pub failed: Vec<(String, E)>,
+    pub skipped: Vec<String>,
+}
+
+/// run `job` for each project of `order` (the project and its dependencies, each project comes
+/// after its dependencies) on up to `jobs` threads. a project is started when all its
+/// dependencies finished, and `job` gets the results of the dependencies.
+///
+/// after a failure, no more projects are started unless `keep_going`, the projects depending on
+/// a failed project are always skipped. the running projects are not cancelled, they are waited
+/// for.
+///
+/// a panic of `job` is a failure which is not kept going after, it is resumed when the running
+/// projects finished.
+pub fn schedule<T, E>(
+    order: &[(String, Vec<String>)],
+    jobs: usize,
+    keep_going: bool,
+    job: impl Fn(&str, Vec<T>) -> std::result::Result<T, E> + Sync,
+) -> Schedule<T, E>
+where
+    T: Clone + Send,
+    E: Send,
+{
+    let mut schedule: Schedule<T, E> = Schedule {
+        finished: BTreeMap::new(),
+        failed: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let job = &job;
+    let mut panic = None;
+    std::thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let mut pending = order.iter().collect::<Vec<_>>();
+        let mut running = 0;
+        loop {
+            let mut index = 0;
+            while index < pending.len() && running < jobs.max(1) {
+                let (name, dependencies) = pending[index];
+                let failed = |dependency: &String| {
+                    schedule.skipped.contains(dependency)
+                        || schedule.failed.iter().any(|(name, _)| name == dependency)
+                };
+                if dependencies.iter().any(failed) {
+                    schedule.skipped.push(name.clone());
+                    pending.remove(index);
+                } else if dependencies
+                    .iter()
+                    .all(|dependency| schedule.finished.contains_key(dependency))
+                {
+                    let results = dependencies
+                        .iter()
+                        .map(|dependency| schedule.finished[dependency].clone())
+                        .collect();
+                    let sender = sender.clone();
+                    scope.spawn(move || {
+                        // the result is always sent, so a panic does not block the receiver
+                        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                            job(name, results)
+                        }));
+                        let _ = sender.send((name, result));
+                    });
+                    running += 1;
+                    pending.remove(index);
+                } else {
+                    index += 1;
+                }
+            }
+            if running == 0 {
+                break;
+            }
+            let Ok((name, result)) = receiver.recv() else {
+                break;
+            };
+            running -= 1;
+            match result {
+                Ok(Ok(result)) => {
+                    schedule.finished.insert(name.clone(), result);
+                }
+                Ok(Err(e)) => {
+                    schedule.failed.push((name.clone(), e));
+                    if !keep_going {
+                        schedule
+                            .skipped
+                            .extend(pending.drain(..).map(|(name, _)| name.clone()));
+                    }
+                }
+                Err(payload) => {
+                    panic.get_or_insert(payload);
+                    schedule
+                        .skipped
+                        .extend(pending.drain(..).map(|(name, _)| name.clone()));
+                }
+            }
+        }
+    });
+    if let Some(payload) = panic {
+        std::panic::resume_unwind(payload);
+    }
+    schedule
+}
+
+/// the profile directory (`target/<profile>`, or `target/<triple>/<profile>`)
+pub fn profile_dir(workspace_dir: &Path, target: Option<&str>, profile: &str) -> PathBuf {
+    let target_dir = workspace_dir.join(TARGET_DIR_NAME);
+    match target {
+        Some(target) => target_dir.join(target).join(profile),
+        None => target_dir.join(profile),
+    }
+}
+
+/// `name` in uppercase, non-alphanumeric characters are replaced by `_`
+pub fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// the projects in `roots` and their dependencies (recursively), each project comes after its
+/// dependencies
+pub fn build_order(projects: &BTreeMap<String, Project>, roots: &[String]) -> Result<Vec<String>> {
+    fn visit(
+        projects: &BTreeMap<String, Project>,
+        name: &str,
+        visiting: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<()> {
+        if order.iter().any(|visited| visited == name) {
+            return Ok(());
+        }
+        if let Some(index) = visiting.iter().position(|visiting| visiting == name) {
+            let mut cycle = visiting[index..].to_vec();
+            cycle.push(name.to_owned());
+            return Err(Error::DependencyCycleError(cycle));
+        }
+        let project = &projects[name];
+        visiting.push(name.to_owned());
+        for dependency in &project.config.config.dependencies {
+            if !projects.contains_key(dependency) {
+                return Err(Error::UnknownDependencyError {
+                    project: name.to_owned(),
+                    dependency: dependency.clone(),
+                });
+            }
+            visit(projects, dependency, visiting, order)?;
+        }
+        visiting.pop();
+        order.push(name.to_owned());
+        Ok(())
+    }
+    let mut order = Vec::new();
+    for root in roots {
+        visit(projects, root, &mut Vec::new(), &mut order)?;
+    }
+    Ok(order)
+}
+
+/// the backend of the build tool for the language
+pub fn backend(language: Language, build_tool: BuildTool) -> Result<Box<dyn Backend>> {
+    if !language.supports(build_tool) {
+        return Err(Error::UnsupportedBuildToolError(build_tool));
+    }
+    match build_tool {
+        BuildTool::Cargo => Ok(Box::new(crate::lang::cargo::Cargo)),
+        BuildTool::Make => Ok(Box::new(crate::lang::make::Make)),
+        BuildTool::CMake => Ok(Box::new(crate::lang::cmake::CMake)),
+        BuildTool::MSBuild => Ok(Box::new(crate::lang::msbuild::MSBuild)),
+    }
+}
+
+/// the first line of `<program> [arguments...] --version`, run in the project directory, which may
+/// select the version (e.g. `rust-toolchain.toml`)
+pub fn tool_version(context: &Context, program: &str, arguments: &[&str]) -> Result<String> {
+    let output = context
+        .command(program)
+        .current_dir(&context.project.dir)
+        .args(arguments)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::ToolNotFoundError(program.to_owned()),
+            _ => Error::IOError(PathBuf::from(program), e),
+        })?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or_default().trim().to_owned())
+}
+
+/// the first line of `<compiler> --version`, `compiler` is a command line (e.g. `ccache gcc`).
+/// if a target is built for, a missing compiler is a missing toolchain of the target.
+pub fn compiler_version(context: &Context, compiler: &str) -> Result<String> {
+    let mut command = compiler.split_whitespace();
+    let program = command.next().unwrap_or_default();
+    let arguments = command.collect::<Vec<_>>();
+    tool_version(context, program, &arguments).map_err(|e| match (e, context.target) {
+        (Error::ToolNotFoundError(tool), Some(target)) => Error::TargetNotInstalledError {
+            target: target.triple.clone(),
+            tool: format!("the compiler '{}'", tool),
+        },
+        (e, _) => e,
+    })
+}
+
+/// the command line of `command` (the arguments with spaces are quoted)
+pub fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|argument| {
+            let argument = argument.to_string_lossy();
+            match argument.is_empty() || argument.contains(char::is_whitespace) {
+                true => format!("'{}'", argument),
+                false => argument.into_owned(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// run the build tool, and pass each line of its stdout to `on_line`, stderr is written to the
+/// output of the context
+pub fn run_tool(
+    context: &Context,
+    command: &mut Command,
+    tool: &str,
+    mut on_line: impl FnMut(&str),
+) -> Result<ExitStatus> {
+    let io_error = |e| Error::IOError(PathBuf::from(tool), e);
+    let stderr =
+        match context.output.is_buffered() || crate::message::format() == MessageFormat::Json {
+            true => Stdio::piped(),
+            false => Stdio::inherit(),
+        };
+    context.output.emit(Event::Command {
+        project: context.project.name.clone(),
+        command: command_line(command),
+    });
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(stderr)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::ToolNotFoundError(tool.to_owned()),
+            _ => io_error(e),
+        })?;
+    let (sender, receiver) = mpsc::channel();
+    std::thread::scope(|scope| {
+        if let Some(stderr) = child.stderr.take() {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                if let Err(e) =
+                    read_lines(stderr, |line| sender.send(Ok(Line::Stderr(line))).is_ok())
+                {
+                    let _ = sender.send(Err(e));
+                }
+            });
+        }
+        if let Some(stdout) = child.stdout.take() {
+            scope.spawn(move || {
+                if let Err(e) =
+                    read_lines(stdout, |line| sender.send(Ok(Line::Stdout(line))).is_ok())
+                {
+                    let _ = sender.send(Err(e));
+                }
+            });
+        } else {
+            drop(sender);
+        }
+        for line in receiver {
+            match line.map_err(io_error)? {
+                Line::Stdout(line) => on_line(&line),
+                Line::Stderr(line) => context.output.line(Stream::Stderr, line),
+            }
+        }
+        Ok(())
+    })?;
+    child.wait().map_err(io_error)
+}
+
+/// pass each line of `reader` to `on_line` until it returns false, tools may write anything, so
+/// invalid utf-8 is replaced rather than failing the build
+fn read_lines(reader: impl io::Read, mut on_line: impl FnMut(String) -> bool) -> io::Result<()> {
+    let mut reader = io::BufReader::new(reader);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        if !on_line(String::from_utf8_lossy(text).into_owned()) {
+            break;
+        }
+        line.clear();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang::Language;
+
+    fn projects(dependencies: &[(&str, &[&str])]) -> BTreeMap<String, Project> {
+        dependencies
+            .iter()
+            .map(|&(name, dependencies)| {
+                let mut config = ProjectConfig::new(name, Language::C);
+                config.config.dependencies = dependencies.iter().map(|d| d.to_string()).collect();
+                let project = Project {
+                    name: name.to_owned(),
+                    dir: PathBuf::from(name),
+                    config,
+                };
+                (name.to_owned(), project)
+            })
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn build_order_dependencies_first() {
+        let projects = projects(&[("app", &["lib", "util"]), ("lib", &["util"]), ("util", &[])]);
+        let order = build_order(&projects, &names(&["app"])).unwrap();
+        assert_eq!(order, names(&["util", "lib", "app"]));
+        let order = build_order(&projects, &names(&["lib", "app"])).unwrap();
+        assert_eq!(order, names(&["util", "lib", "app"]));
+    }
+
+    #[test]
+    fn build_order_cycle() {
+        let projects = projects(&[("a", &["b"]), ("b", &["c"]), ("c", &["b"])]);
+        match build_order(&projects, &names(&["a"])) {
+            Err(Error::DependencyCycleError(cycle)) => assert_eq!(cycle, names(&["b", "c", "b"])),
+            result => panic!("expected a cycle, found {:?}", result),
+        }
+    }
+
+    #[test]
+    fn build_order_unknown_dependency() {
+        let projects = projects(&[("a", &["missing"])]);
+        assert!(matches!(
+            build_order(&projects, &names(&["a"])),
+            Err(Error::UnknownDependencyError { project, dependency })
+                if project == "a" && dependency == "missing"
+        ));
+    }
+
+    fn order(order: &[(&str, &[&str])]) -> Vec<(String, Vec<String>)> {
+        order
+            .iter()
+            .map(|&(name, dependencies)| (name.to_owned(), names(dependencies)))
+            .collect()
+    }
+
+    #[test]
+    fn schedule_passes_the_results_of_the_dependencies() {
+        let order = order(&[("util", &[]), ("lib", &["util"]), ("app", &["lib", "util"])]);
+        let schedule = schedule(&order, 4, false, |name, results: Vec<String>| {
+            Ok::<_, ()>(format!("{}({})", name, results.join(",")))
+        });
+        assert_eq!(schedule.finished["app"], "app(lib(util()),util())");
+        assert!(schedule.failed.is_empty());
+        assert!(schedule.skipped.is_empty());
+    }
+
+    #[test]
+    fn schedule_stops_after_a_failure() {
+        let order = order(&[("util", &[]), ("other", &[]), ("app", &["util"])]);
+        let schedule = schedule(&order, 1, false, |name, _: Vec<()>| match name {
+            "util" => Err(name.to_owned()),
+            _ => Ok(()),
+        });
+        assert!(schedule.finished.is_empty());
+        assert_eq!(
+            schedule.failed,
+            vec![("util".to_owned(), "util".to_owned())]
+        );
+        assert_eq!(schedule.skipped, names(&["other", "app"]));
+    }
+
+    #[test]
+    fn schedule_keep_going_skips_only_the_dependents() {
+        let order = order(&[("util", &[]), ("other", &[]), ("app", &["util"])]);
+        let schedule = schedule(&order, 1, true, |name, _: Vec<()>| match name {
+            "util" => Err(()),
+            _ => Ok(()),
+        });
+        assert_eq!(schedule.finished.keys().collect::<Vec<_>>(), ["other"]);
+        assert_eq!(schedule.skipped, names(&["app"]));
+    }
+
+    #[test]
+    fn schedule_resumes_a_panic_after_the_running_jobs() {
+        let order = order(&[("util", &[]), ("other", &[]), ("app", &["util"])]);
+        let finished = Mutex::new(Vec::new());
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            schedule(&order, 2, true, |name, _: Vec<()>| {
+                if name == "util" {
+                    panic!("job panicked");
+                }
+                finished.lock().unwrap().push(name.to_owned());
+                Ok::<_, ()>(())
+            })
+        }));
+        assert_eq!(
+            panic.unwrap_err().downcast_ref::<&str>(),
+            Some(&"job panicked")
+        );
+        assert_eq!(*finished.lock().unwrap(), ["other"]);
+    }
+}
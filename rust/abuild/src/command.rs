@@ -21,10 +21,19 @@
 //! > > the auto-completion script for zsh was installed in '/usr/local/share/zsh/site-functions/_abuild' successfully.
 //! > > $ abuild auto-complete remove bash
 //! > > the auto-completion script for bash was removed from '/etc/bash_completion.d/abuild' successfully.
+//! > > $ abuild auto-complete install fish --user
+//! > > the auto-completion script for fish will be installed in '~/.config/fish/completions/abuild.fish'.
 //! > > ```
+//! > > + ![note] `--user` uses the directory of the current user (bash:
+//! > >   `~/.local/share/bash-completion/completions`, zsh: `~/.local/share/zsh/site-functions`,
+//! > >   which must be in `fpath`, fish: `~/.config/fish/completions`, powershell:
+//! > >   `~/.local/share/powershell/Modules`, elvish: `~/.config/elvish/lib`), which is also used
+//! > >   if the system directory is not writable.
 //! >
 //! > > ![feature] init/create/remove (workspace|project|profile)
 //! > > + ![note] init profile: unsupported yet.
+//! > > + ![note] create profile: the built-in profiles `debug` and `release` always exist, a new
+//! > >   profile inherits `debug` unless `--inherits` is provided.
 //! > > + ![note] init/create: The workspace directory must be empty.
 //! > > + ![note] create/remove profile: The current folder must be a (workspace|project), or the (-w|-j) option must be provided.
 //! > > ```shell
@@ -32,6 +41,8 @@
 //! > > workspace '<current_directory>' was initialized successfully.
 //! > > $ abuild create
 //! > > workspace '<workspace_name>' was created successfully.
+//! > > $ abuild create -p release-lto --inherits release
+//! > > profile 'release-lto' was created successfully.
 //! > > $ abuild remove
 //! > > workspace '<workspace_name>' was removed successfully.
 //! > > ```
@@ -50,15 +61,27 @@
 //! > > ```shell
 //! > > $ abuild build
 //! > > building...
+//! > > ... # output of the build process (the projects which were not changed are skipped)
+//! > > building finished.
+//! > > $ abuild build --explain
+//! > > building...
+//! > > project '<project_name>' (<language>, <build_tool>, profile '<profile_name>'):
+//! > >     dirty: file 'src/main.rs' changed.
 //! > > ... # output of the build process
 //! > > building finished.
 //! > > $ abuild clean
 //! > > cleaning...
 //! > > ... # output of the clean process
-//! > > cleaning finished.
+//! > > cleaning finished, <size> freed.
+//! > > $ abuild clean -j my-project --dry-run
+//! > > cleaning...
+//! > >     would remove '<path>' (<files> files, <size>).
+//! > > cleaning finished, <size> would be freed.
 //! > > ```
 //! >
 //! > > ![feature] run (workspace|project|profile)
+//! > > + ![note] run: abuild exits with the exit code of the binary (the first non-zero one if
+//! > >   several binaries were run, `124` if it was killed by `--timeout`).
 //! > > ```shell
 //! > > $ abuild run
 //! > > ... # output of the build process (if not already built)
@@ -67,28 +90,98 @@
 //! > > the program is exited with code '<exit_code>'.
 //! > > ```
 //! >
+//! > > ![feature] gc (workspace|project|profile)
+//! > > + ![note] gc: the last use of the build outputs is recorded by build and run (see
+//! > >   [`crate::gc`]).
+//! > > ```shell
+//! > > $ abuild gc --max-size 20G --older-than 14d
+//! > > collecting garbage...
+//! > >     removed '<path>' (<files> files, <size>).
+//! > > collecting garbage finished, <size> freed.
+//! > > ```
+//! >
 //! > > ![feature] rebuild = clean \& build (workspace|project|profile)
+//! > > + ![note] not supported yet: `abuild rebuild` fails, run `abuild clean` and then
+//! > >   `abuild build` instead.
 //! > > ```shell
-//! > > $ abuild rebuild
+//! > > $ abuild clean && abuild build
 //! > > ... # output of the clean process
 //! > > ... # output of the build process
 //! > > ```
 //! >
-//! > > ![feature] set/unset (workspace|project|profile)
+//! > > ![feature] set/unset/get (workspace|project|profile)
+//! > > + ![note] set/unset: the value is a toml value (or a string), the edited config is
+//! > >   checked against the config schema, its comments and formatting are preserved, and the
+//! > >   change can be undone.
+//! > > + ![note] set/unset/get profile: the key is relative to `[profile.<profile_name>]`.
 //! > > ```shell
-//! > > $ abuild set -w . config.author "your_name"
-//! > > the workspace '<workspace_name>' config.author was set to "your_name".
+//! > > $ abuild set -w . config.authors '["your_name"]'
+//! > > the workspace '<workspace_name>' config.authors was set to ["your_name"].
 //! > > $ abuild set -j my_project config.version "1.0.0"
 //! > > the project '<project_name>' config.version was set to "1.0.0".
+//! > > $ abuild set -p release-lto opt-level 3
+//! > > the profile 'release-lto' opt-level was set to 3.
+//! > > $ abuild unset -p release-lto opt-level
+//! > > the profile 'release-lto' opt-level was unset.
+//! > > $ abuild get -j my_project config.version
+//! > > 1.0.0
+//! > > ```
+//! >
+//! > > ![feature] config show (workspace|project|profile)
+//! > > + ![note] config show: the config is resolved from the user config, the workspace, the
+//! > >   project, the `ABUILD_*` environment variables and the `--config` options (see
+//! > >   [`crate::config::layer`]).
+//! > > ```shell
+//! > > $ abuild config show --origin -p release --config profile.release.debug=true
+//! > > profile.release.debug = true (cli '--config profile.release.debug=true')
+//! > > profile.release.opt-level = 2 (user '~/.config/abuild/config.toml')
 //! > > ```
 //! >
 //!
 //! # Options:
-//! - `-w, --workspace <workspace_path>`: set the workspace directory.
-//! - `-j, --project <project_name>`: set the project name.
+//! - `-w, --workspace <workspace_path>`: set the workspace directory (default: `ABUILD_WORKSPACE`,
+//!   or the nearest workspace which contains the current directory).
+//! - `-j, --project <project_name>`: set the project name (default: the project which contains
+//!   the current directory, unless `-w` or `ABUILD_WORKSPACE` is provided).
 //! - `-p, --profile <profile_name>`: set the profile name.
+//! - `--user`: install (or reinstall, remove) the auto-completion script for the current user.
+//! - `--config <KEY=VALUE>`: override a config key (e.g. `profile.release.opt-level=2`).
+//! - `--origin`: print the layer each config value comes from.
+//! - `-l, --language <language>`: set the language of the project to create.
+//! - `--build-tool <build_tool>`: set the build tool of the project to create.
 //! - `-b, --binary <binary_name>`: set the binary name to build or run.
 //! - `-a, --args <args>`: set the arguments to pass to the binary.
+//! - `--cwd <dir>`: set the working directory of the binary to run.
+//! - `--env <KEY=VALUE>`: set an environment variable of the binary to run.
+//! - `--timeout <duration>`: kill the binary to run after the duration (e.g. `30s`, `5m`).
+//! - `--dry-run`: print what clean (or gc) would remove, without removing it.
+//! - `--max-size <size>`: set the size budget of the target directory for gc (e.g. `20G`).
+//! - `--older-than <duration>`: set the age of the build outputs gc removes (e.g. `14d`).
+//! - `--target <triple>`: set the target to build, run or clean for (default: the host).
+//! - `--jobs <jobs>`: set the number of projects to build at the same time (default: the number
+//!   of CPUs).
+//! - `--keep-going`: keep building the other projects after a project failed (without it, no more
+//!   projects are started, and the running projects are finished).
+//! - `--explain`: print why each project was built (the changed file, config key, profile field,
+//!   environment variable or toolchain version), or that it was fresh.
+//! - `--message-format <human|json>`: print colored text (default), or newline-delimited json
+//!   events (see [`crate::message`]).
+//! - `-v, --verbose`: print the command lines of the build tools too (`-vv`: and why each project
+//!   was built).
+//! - `-q, --quiet`: print only warnings, errors, the output of the binaries and the values.
+//! - `--color <auto|always|never>`: when to use colors (default: `auto`, which honors `NO_COLOR`).
+//!
+//! # Exit codes:
+//! - `0`: success.
+//! - `1`: a build failed, or no binary to run was built.
+//! - `2`: invalid arguments (e.g. an invalid name, an unsupported shell, nothing to undo).
+//! - `3`: an invalid config (e.g. a parse error, a dependency cycle).
+//! - `4`: the workspace, project or profile was not found.
+//! - `5`: a build tool or compiler (of the target) was not found.
+//! - `6`: a file operation failed.
+//! - run: the exit code of the binary.
+//!
+//! + ![note] errors are printed with a hint how to fix them, if known.
 //!
 //! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
 //!
@@ -97,35 +190,310 @@
 //! [feature]: https://img.shields.io/badge/feature-orange.svg
 //!
 
-use clap::{value_parser, CommandFactory, Parser, Subcommand, ValueHint};
-use clap_complete::aot::generate;
+use crate::build::{Artifact, ArtifactKind, Context, Dependency, Output, Project};
+use crate::config::layer::Layers;
+use crate::config::{Profile, ProfileConfig, ProjectConfig, Target, WorkspaceConfig};
+use crate::fingerprint::Fingerprint;
+use crate::fs::DiskUsage;
+use crate::journal::{Journal, Recorder};
+use crate::lang::{BuildTool, Language};
+use crate::message;
+use crate::message::{ColorChoice, Event, Level, MessageFormat, Operation, Stream, Verbosity};
+use crate::template::Template;
+use crate::trash::Moved;
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueHint, value_parser};
 use clap_complete::Generator;
+use clap_complete::aot::generate;
 use colored::Colorize;
-use io::Write;
+use io::{BufRead, Write};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::ffi::OsString;
 use std::fmt::{Debug, Display, Formatter};
 use std::io;
 use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+/// the environment variable which overrides the workspace directory (unless `-w` is provided)
+pub const WORKSPACE_ENV: &str = "ABUILD_WORKSPACE";
+
+/// the exit code of a failed build (or `run` without a built binary)
+pub const EXIT_BUILD_FAILED: u8 = 1;
+/// the exit code of invalid arguments (e.g. an invalid name, an unsupported shell)
+pub const EXIT_USAGE: u8 = 2;
+/// the exit code of an invalid config
+pub const EXIT_CONFIG: u8 = 3;
+/// the exit code of a scope which was not found (workspace, project, profile)
+pub const EXIT_SCOPE: u8 = 4;
+/// the exit code of a build tool or compiler which was not found
+pub const EXIT_TOOLCHAIN: u8 = 5;
+/// the exit code of a failed file operation
+pub const EXIT_IO: u8 = 6;
 
 #[derive(Debug)]
 pub enum Error {
-    IOError(io::Error),
+    IOError {
+        /// what was done with the path (e.g. `create`, `remove`)
+        operation: &'static str,
+        path: PathBuf,
+        error: io::Error,
+    },
+    ConfigError(crate::config::Error),
+    JournalError(crate::journal::Error),
+    BuildError(crate::build::Error),
+    FingerprintError(crate::fingerprint::Error),
+    ProjectsFailedError(Vec<(String, Error)>),
     NoHomeDirError,
+    NotEmptyDirError(PathBuf),
+    NotWorkspaceError(PathBuf),
+    NotProjectError(PathBuf),
+    InvalidNameError(String),
+    AlreadyExistsError(String),
+    TemplateNotFoundError(String, Option<PathBuf>),
+    UnsupportedBuildToolError(Language, BuildTool),
+    NothingToUndoError,
+    NothingToRedoError,
+    BinaryNotFoundError(Option<String>),
+    UnsupportedError(&'static str),
+    UnsupportedShellError(Shell),
+    NotInstalledError(Shell, PathBuf),
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::IOError(e) => write!(f, "IO error: {}", e),
+            Error::IOError {
+                operation,
+                path,
+                error,
+            } => write!(
+                f,
+                "IO error: failed to {} '{}': {}",
+                operation,
+                path.display(),
+                error
+            ),
+            Error::ConfigError(e) => write!(f, "Config error: {}", e),
+            Error::JournalError(e) => write!(f, "Journal error: {}", e),
+            Error::BuildError(e) => write!(f, "Build error: {}", e),
+            Error::FingerprintError(e) => write!(f, "Fingerprint error: {}", e),
+            Error::ProjectsFailedError(errors) => {
+                write!(f, "{} projects failed:", errors.len())?;
+                for (project, e) in errors {
+                    write!(f, "\n    project '{}': {}", project, e)?;
+                }
+                Ok(())
+            }
             Error::NoHomeDirError => write!(f, "No home directory found"),
+            Error::NotEmptyDirError(path) => {
+                write!(f, "The directory '{}' is not empty", path.display())
+            }
+            Error::NotWorkspaceError(path) => {
+                write!(f, "The directory '{}' is not a workspace", path.display())
+            }
+            Error::NotProjectError(path) => {
+                write!(f, "The directory '{}' is not a project", path.display())
+            }
+            Error::InvalidNameError(name) => write!(f, "Invalid name: '{}'", name),
+            Error::AlreadyExistsError(name) => write!(f, "'{}' already exists", name),
+            Error::TemplateNotFoundError(name, Some(dir)) => write!(
+                f,
+                "The template '{}' was not found in '{}'",
+                name,
+                dir.display()
+            ),
+            Error::TemplateNotFoundError(name, None) => {
+                write!(f, "The template '{}' was not found", name)
+            }
+            Error::UnsupportedBuildToolError(language, build_tool) => write!(
+                f,
+                "The build tool '{}' is not supported for the language '{}'",
+                build_tool, language
+            ),
+            Error::NothingToUndoError => write!(f, "There is nothing to undo"),
+            Error::NothingToRedoError => write!(f, "There is nothing to redo"),
+            Error::BinaryNotFoundError(Some(binary)) => {
+                write!(f, "The binary '{}' was not found", binary)
+            }
+            Error::BinaryNotFoundError(None) => write!(f, "No binary was built"),
+            Error::UnsupportedError(what) => write!(f, "Unsupported yet: {}", what),
+            Error::UnsupportedShellError(shell) => {
+                write!(f, "The shell '{}' is not supported", shell)
+            }
+            Error::NotInstalledError(shell, path) => write!(
+                f,
+                "The auto-completion script for {} was not installed in '{}'",
+                shell,
+                path.display()
+            ),
         }
     }
 }
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            Error::IOError(e) => Some(e),
-            Error::NoHomeDirError => None,
+            Error::IOError { error, .. } => Some(error),
+            Error::ConfigError(e) => Some(e),
+            Error::JournalError(e) => Some(e),
+            Error::BuildError(e) => Some(e),
+            Error::FingerprintError(e) => Some(e),
+            Error::ProjectsFailedError(_) => None,
+            Error::NoHomeDirError
+            | Error::NotEmptyDirError(_)
+            | Error::NotWorkspaceError(_)
+            | Error::NotProjectError(_)
+            | Error::InvalidNameError(_)
+            | Error::AlreadyExistsError(_)
+            | Error::TemplateNotFoundError(..)
+            | Error::UnsupportedBuildToolError(..)
+            | Error::NothingToUndoError
+            | Error::NothingToRedoError
+            | Error::BinaryNotFoundError(_)
+            | Error::UnsupportedError(_)
+            | Error::UnsupportedShellError(_)
+            | Error::NotInstalledError(..) => None,
+        }
+    }
+}
+
+impl Error {
+    /// the IO error of `operation` on `path`
+    pub fn io(operation: &'static str, path: impl AsRef<Path>) -> impl FnOnce(io::Error) -> Self {
+        let path = path.as_ref().to_path_buf();
+        move |error| Error::IOError {
+            operation,
+            path,
+            error,
+        }
+    }
+    /// the exit code of abuild for the error (see the `EXIT_*` constants)
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::IOError { .. } | Error::JournalError(_) | Error::FingerprintError(_) => EXIT_IO,
+            Error::ConfigError(crate::config::Error::IOError(..)) => EXIT_IO,
+            Error::ConfigError(crate::config::Error::ProfileNotFoundError(_)) => EXIT_SCOPE,
+            Error::ConfigError(_) => EXIT_CONFIG,
+            Error::BuildError(e) => match e {
+                crate::build::Error::IOError(..) => EXIT_IO,
+                crate::build::Error::ToolNotFoundError(_)
+                | crate::build::Error::TargetNotInstalledError { .. } => EXIT_TOOLCHAIN,
+                crate::build::Error::UnknownDependencyError { .. }
+                | crate::build::Error::DependencyCycleError(_)
+                | crate::build::Error::UnsupportedBuildToolError(..)
+                | crate::build::Error::BinaryConflictError { .. } => EXIT_CONFIG,
+                crate::build::Error::FailedError { .. }
+                | crate::build::Error::BinaryNotFoundError { .. } => EXIT_BUILD_FAILED,
+            },
+            Error::ProjectsFailedError(errors) => errors
+                .first()
+                .map_or(EXIT_BUILD_FAILED, |(_, e)| e.exit_code()),
+            Error::BinaryNotFoundError(_) => EXIT_BUILD_FAILED,
+            Error::NotWorkspaceError(_)
+            | Error::NotProjectError(_)
+            | Error::NotInstalledError(..)
+            | Error::NoHomeDirError => EXIT_SCOPE,
+            Error::UnsupportedBuildToolError(..) => EXIT_CONFIG,
+            Error::NotEmptyDirError(_)
+            | Error::InvalidNameError(_)
+            | Error::AlreadyExistsError(_)
+            | Error::TemplateNotFoundError(..)
+            | Error::NothingToUndoError
+            | Error::NothingToRedoError
+            | Error::UnsupportedError(_)
+            | Error::UnsupportedShellError(_) => EXIT_USAGE,
+        }
+    }
+    /// how to fix the error, if known
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            Error::IOError { error, path, .. }
+                if error.kind() == io::ErrorKind::PermissionDenied =>
+            {
+                Some(format!(
+                    "check the permissions of '{}' (installing system-wide may need root, or use \
+                     '--user')",
+                    path.display()
+                ))
+            }
+            Error::ConfigError(crate::config::Error::ProfileNotFoundError(name)) => Some(format!(
+                "run 'abuild create -p {}' to create the profile",
+                name
+            )),
+            Error::ConfigError(crate::config::Error::ParseError { path, .. }) => Some(format!(
+                "fix '{}', or run 'abuild undo' if it was changed by abuild",
+                path.display()
+            )),
+            Error::BuildError(crate::build::Error::ToolNotFoundError(tool)) => Some(format!(
+                "install '{}', and make sure it is in the PATH",
+                tool
+            )),
+            Error::BuildError(crate::build::Error::TargetNotInstalledError { target, tool }) => {
+                Some(match tool.as_str() {
+                    crate::lang::cargo::RUST_STD_NAME => {
+                        format!("run 'rustup target add {}'", target)
+                    }
+                    _ => format!(
+                        "install a cross compiler for '{0}', or set 'target.{0}.cc' in the config",
+                        target
+                    ),
+                })
+            }
+            Error::BuildError(crate::build::Error::FailedError { tool, .. }) => {
+                Some(format!("see the output of {} above", tool))
+            }
+            Error::BuildError(crate::build::Error::DependencyCycleError(_)) => {
+                Some("remove one of the dependencies in 'config.dependencies'".to_owned())
+            }
+            Error::BuildError(crate::build::Error::BinaryConflictError {
+                binary, other, ..
+            }) => Some(format!(
+                "rename one of the binaries, or run 'abuild clean -j {}' if it does not build \
+                     '{}' any more",
+                other, binary
+            )),
+            Error::ProjectsFailedError(errors) => errors.iter().find_map(|(_, e)| e.hint()),
+            Error::NotWorkspaceError(_) => Some(format!(
+                "run 'abuild init' to initialize a workspace, or pass '-w <workspace_path>' (or \
+                 set '{}')",
+                WORKSPACE_ENV
+            )),
+            Error::NotProjectError(path) => path.file_name().map(|name| {
+                format!(
+                    "run 'abuild create -j {}' to create the project",
+                    name.to_string_lossy()
+                )
+            }),
+            Error::NotEmptyDirError(_) => Some(
+                "use an empty directory, or 'abuild create -j <project_name>' to add a project"
+                    .to_owned(),
+            ),
+            Error::BinaryNotFoundError(Some(_)) => {
+                Some("run 'abuild build' to list the built binaries".to_owned())
+            }
+            Error::NoHomeDirError => Some("set the 'HOME' environment variable".to_owned()),
+            Error::UnsupportedShellError(_) => Some(format!(
+                "the supported shells are {}, {}, {}, {} and {}",
+                Shell::Bash,
+                Shell::Zsh,
+                Shell::Fish,
+                Shell::PowerShell,
+                Shell::Elvish
+            )),
+            Error::NotInstalledError(shell, path) => {
+                let options = ShellOptions {
+                    shell: *shell,
+                    user: false,
+                };
+                let user = options
+                    .config_file_path(CompletionLocation::System)
+                    .is_ok_and(|system_path| system_path != path.as_path());
+                Some(format!(
+                    "run 'abuild auto-complete install {}{}'",
+                    shell,
+                    if user { " --user" } else { "" }
+                ))
+            }
+            _ => None,
         }
     }
 }
@@ -135,6 +503,19 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug, Clone, PartialEq, Eq, Parser)]
 #[command(version, about, author, long_about = None)]
 pub struct Cli {
+    /// the format of the messages
+    #[clap(long, global = true, value_enum, default_value_t)]
+    message_format: MessageFormat,
+    /// print more messages (`-v`: the command lines of the build tools, `-vv`: why each project
+    /// was built)
+    #[clap(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// print only warnings, errors and the results
+    #[clap(short, long, global = true)]
+    quiet: bool,
+    /// when to use colors
+    #[clap(long, global = true, value_enum, default_value_t)]
+    color: ColorChoice,
     #[command(subcommand)]
     sub_command: SubCommand,
 }
@@ -146,6 +527,19 @@ pub struct ShellOptions {
     /// the shell to generate the auto-completion script for
     #[clap(value_parser=value_parser!(Shell))]
     shell: Shell,
+    /// use the directory of the current user (default: the system directory, or the directory
+    /// of the current user if the system directory is not writable)
+    #[clap(long)]
+    user: bool,
+}
+
+/// where the auto-completion script is installed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionLocation {
+    /// the system directory of the shell (e.g. `/etc/bash_completion.d`)
+    System,
+    /// the directory of the current user (e.g. `~/.local/share/bash-completion/completions`)
+    User,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
@@ -167,8 +561,21 @@ pub enum AutoCompleteSubCommand {
     },
     /// output auto-completion script
     Output {
+        /// the shell to generate the auto-completion script for
+        #[clap(value_parser=value_parser!(Shell))]
+        shell: Shell,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum ConfigSubCommand {
+    /// print the effective config of a workspace or project or profile
+    Show {
+        /// print the layer each value comes from
+        #[clap(long)]
+        origin: bool,
         #[clap(flatten)]
-        shell: ShellOptions,
+        scope: ScopeOptions,
     },
 }
 
@@ -186,10 +593,22 @@ pub enum SubCommand {
     },
     /// create a new workspace or project or profile
     Create {
+        /// the language of the project (default: rust)
+        #[clap(short, long)]
+        language: Option<Language>,
+        /// the build tool of the project (default: the default build tool of the language)
+        #[clap(long)]
+        build_tool: Option<BuildTool>,
+        /// the user template to create the project from (default: the built-in template)
+        #[clap(long, value_hint = ValueHint::Other)]
+        template: Option<String>,
+        #[clap(flatten)]
+        profile_options: ProfileOptions,
         #[clap(flatten)]
         scope: ScopeOptions,
     },
-    /// remove a workspace or project or profile
+    /// remove a workspace or project or profile (of the workspace in the current directory or
+    /// `-w`, never discovered from a parent directory)
     Remove {
         #[clap(flatten)]
         scope: ScopeOptions,
@@ -209,11 +628,25 @@ pub enum SubCommand {
         /// the binary to build (default: build all binaries)
         #[clap(short, long)]
         binary: Option<String>,
+        /// the target triple to build for (default: the host)
+        #[clap(long, value_hint = ValueHint::Other)]
+        target: Option<String>,
+        /// print why each project was built (or that it was fresh)
+        #[clap(long)]
+        explain: bool,
+        #[clap(flatten)]
+        job_options: JobOptions,
         #[clap(flatten)]
         scope: ScopeOptions,
     },
     /// clean workspace or project or profile
     Clean {
+        /// the target triple to clean (default: the host)
+        #[clap(long, value_hint = ValueHint::Other)]
+        target: Option<String>,
+        /// print what would be removed, without removing it
+        #[clap(long)]
+        dry_run: bool,
         #[clap(flatten)]
         scope: ScopeOptions,
     },
@@ -226,9 +659,32 @@ pub enum SubCommand {
         #[clap(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<OsString>,
         #[clap(flatten)]
+        run_options: RunOptions,
+        #[clap(flatten)]
+        job_options: JobOptions,
+        /// the target triple to run (default: the host)
+        #[clap(long, value_hint = ValueHint::Other)]
+        target: Option<String>,
+        #[clap(flatten)]
         scope: ScopeOptions,
     },
-    /// rebuild workspace or project or profile
+    /// remove the least recently used build outputs of a workspace
+    #[clap(group(ArgGroup::new("limits").required(true).multiple(true)))]
+    Gc {
+        /// remove the least recently used outputs until the target directory is not larger
+        /// than the size (e.g. `20G`, `500M`)
+        #[clap(long, group = "limits", value_parser = parse_size)]
+        max_size: Option<u64>,
+        /// remove the outputs which were not used for the duration (e.g. `14d`, `12h`)
+        #[clap(long, group = "limits", value_parser = parse_duration)]
+        older_than: Option<Duration>,
+        /// print what would be removed, without removing it
+        #[clap(long)]
+        dry_run: bool,
+        #[clap(flatten)]
+        scope: ScopeOptions,
+    },
+    /// rebuild workspace or project or profile (not supported yet)
     Rebuild {
         /// the binary to rebuild (default: rebuild all binaries)
         #[clap(short, long)]
@@ -236,6 +692,147 @@ pub enum SubCommand {
         #[clap(flatten)]
         scope: ScopeOptions,
     },
+    /// set a dotted config key (e.g. `config.version`) of a workspace or project or profile
+    Set {
+        /// the dotted key (relative to `[profile.<name>]` with `-p`)
+        #[clap(value_hint = ValueHint::Other)]
+        key: String,
+        /// the value (a toml value, e.g. `1`, `true`, `'["a", "b"]'`, or a string)
+        #[clap(value_hint = ValueHint::Other, allow_hyphen_values = true)]
+        value: String,
+        #[clap(flatten)]
+        scope: ScopeOptions,
+    },
+    /// unset a dotted config key of a workspace or project or profile
+    Unset {
+        /// the dotted key (relative to `[profile.<name>]` with `-p`)
+        #[clap(value_hint = ValueHint::Other)]
+        key: String,
+        #[clap(flatten)]
+        scope: ScopeOptions,
+    },
+    /// print a dotted config key of a workspace or project or profile
+    Get {
+        /// the dotted key (relative to `[profile.<name>]` with `-p`)
+        #[clap(value_hint = ValueHint::Other)]
+        key: String,
+        #[clap(flatten)]
+        scope: ScopeOptions,
+    },
+    /// the layered config (user, workspace, project, env, cli)
+    Config {
+        #[clap(subcommand)]
+        sub_command: ConfigSubCommand,
+    },
+}
+
+/// Options of the profile to create
+#[derive(Default, Debug, Clone, PartialEq, Eq, Parser)]
+pub struct ProfileOptions {
+    /// the profile to inherit from (default: debug)
+    #[clap(long, value_hint = ValueHint::Other)]
+    pub inherits: Option<String>,
+    /// the optimization level of the profile
+    #[clap(long, value_parser = value_parser!(u8).range(0..=3))]
+    pub opt_level: Option<u8>,
+    /// whether the profile generates debug info
+    #[clap(long)]
+    pub debug_info: Option<bool>,
+    /// the preprocessor define (`NAME` or `NAME=VALUE`) of the profile
+    #[clap(short = 'D', long = "define", value_name = "DEFINE")]
+    pub defines: Vec<String>,
+    /// the environment variable (`KEY=VALUE`) passed to the build tool
+    #[clap(long = "env", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    pub env: Vec<(String, String)>,
+}
+
+fn parse_key_value(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_owned(), value.to_owned())),
+        _ => Err(format!("expected KEY=VALUE, found '{}'", value)),
+    }
+}
+
+/// Options of the binaries to run
+#[derive(Default, Debug, Clone, PartialEq, Eq, Parser)]
+pub struct RunOptions {
+    /// the working directory of the binary (default: the current directory)
+    #[clap(long, value_hint = ValueHint::DirPath)]
+    pub cwd: Option<PathBuf>,
+    /// the environment variable (`KEY=VALUE`) passed to the binary
+    #[clap(long = "env", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    pub env: Vec<(String, String)>,
+    /// kill the binary after the duration (e.g. `30s`, `5m`, `500ms`, default unit: seconds)
+    #[clap(long, value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+}
+
+/// parse `<number>[ms|s|m|h|d|w]` (default unit: seconds)
+fn parse_duration(value: &str) -> std::result::Result<Duration, String> {
+    let index = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(index);
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| format!("expected a duration (e.g. 30s), found '{}'", value))?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        "w" => number * 604800.0,
+        _ => {
+            return Err(format!(
+                "unknown unit '{}' (expected ms, s, m, h, d or w)",
+                unit
+            ));
+        }
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|e| e.to_string())
+}
+
+/// parse `<number>[K|M|G|T][i][B]` (powers of 1024, default unit: bytes)
+fn parse_size(value: &str) -> std::result::Result<u64, String> {
+    let index = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(index);
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| format!("expected a size (e.g. 20G), found '{}'", value))?;
+    let unit = unit.trim_end_matches(['B', 'b']).trim_end_matches('i');
+    let scale = match unit.to_ascii_uppercase().as_str() {
+        "" => 1u64,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(format!("unknown unit '{}' (expected K, M, G or T)", unit)),
+    };
+    Ok((number * scale as f64) as u64)
+}
+
+/// Options of the jobs of the build
+#[derive(Default, Debug, Clone, PartialEq, Eq, Parser)]
+pub struct JobOptions {
+    /// the number of projects to build at the same time (default: the number of CPUs)
+    #[clap(long, value_parser = value_parser!(u32).range(1..))]
+    pub jobs: Option<u32>,
+    /// keep building the other projects after a project failed
+    #[clap(long)]
+    pub keep_going: bool,
+}
+
+impl JobOptions {
+    /// the number of projects to build at the same time
+    pub fn jobs(&self) -> usize {
+        match self.jobs {
+            Some(jobs) => jobs as usize,
+            None => std::thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        }
+    }
 }
 
 /// Options for the scope of the command (workspace, project, profile)
@@ -250,19 +847,74 @@ pub struct ScopeOptions {
     /// set the profile name
     #[clap(short, long, value_hint = ValueHint::Unknown)]
     pub profile: Option<String>,
+    /// override a config key (`KEY=VALUE`, e.g. `profile.release.opt-level=2`)
+    #[clap(long = "config", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    pub config: Vec<(String, String)>,
+}
+
+impl ScopeOptions {
+    /// fill in the defaults of the scope:
+    ///
+    /// - the workspace: `ABUILD_WORKSPACE`, or the nearest workspace which contains the current
+    ///   directory (see [`crate::config::discover`])
+    /// - the project: the project of the found workspace which contains the current directory
+    pub fn discover(&mut self) -> Result<()> {
+        if self.workspace.is_some() {
+            return Ok(());
+        }
+        if let Some(workspace) = std::env::var_os(WORKSPACE_ENV).filter(|dir| !dir.is_empty()) {
+            self.workspace = Some(PathBuf::from(workspace));
+            return Ok(());
+        }
+        let current_dir = std::env::current_dir().map_err(Error::io("read", "."))?;
+        if let Some(location) = crate::config::discover(&current_dir).map_err(Error::ConfigError)? {
+            self.workspace = Some(location.workspace_dir);
+            if self.project.is_none() {
+                self.project = location.project;
+            }
+        }
+        Ok(())
+    }
+    /// the workspace directory (default: the current directory)
+    pub fn workspace_dir(&self) -> Result<PathBuf> {
+        match &self.workspace {
+            Some(workspace) => Ok(workspace.clone()),
+            None => std::env::current_dir().map_err(Error::io("read", ".")),
+        }
+    }
 }
 
+/// parse the arguments of the process, exit the process if they are invalid
 pub fn parse_args() -> Cli {
     Cli::parse()
 }
 
+/// parse the arguments of the process, return the clap error if they are invalid (or `--help`
+/// or `--version` was provided)
+pub fn try_parse_args() -> std::result::Result<Cli, clap::Error> {
+    Cli::try_parse()
+}
+
 pub fn generate_completion<G: Generator>(generator: G, bin_name: &str, buf: &mut dyn Write) {
     generate(generator, &mut Cli::command(), bin_name, buf);
 }
 
 impl Cli {
-    pub fn run(&self) -> Result<()> {
-        self.sub_command().run()
+    /// run the command, a reporter which was set with [`message::set_reporter`] is kept
+    pub fn run(&self) -> Result<u8> {
+        message::set_color(self.color);
+        message::set_format(self.message_format);
+        message::set_verbosity(match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::VeryVerbose,
+        });
+        let mut sub_command = self.sub_command().clone();
+        if let Some(scope) = sub_command.discoverable_scope_mut() {
+            scope.discover()?;
+        }
+        sub_command.run()
     }
     pub fn sub_command(&self) -> &SubCommand {
         &self.sub_command
@@ -274,7 +926,10 @@ impl Cli {
 
 impl ShellOptions {
     #[cfg(unix)]
-    pub fn config_dir(&self) -> Result<Cow<'static, Path>> {
+    pub fn config_dir(&self, location: CompletionLocation) -> Result<Cow<'static, Path>> {
+        if location == CompletionLocation::User {
+            return self.user_config_dir().map(Cow::Owned);
+        }
         match self.shell {
             Shell::Bash => Ok(Cow::Borrowed(Path::new("/etc/bash_completion.d"))),
             Shell::Zsh => Ok(Cow::Borrowed(Path::new(
@@ -287,12 +942,35 @@ impl ShellOptions {
                 "/usr/local/share/powershell/Modules/",
             ))),
             Shell::Elvish => Ok(Cow::Borrowed(Path::new("/usr/share/elvish/lib/"))),
-            shell => panic!("unsupported shell: {}", shell),
+            shell => Err(Error::UnsupportedShellError(shell)),
         }
     }
-    pub fn config_file_name(&self) -> Cow<'static, Path> {
+    /// the directory of the current user, which the shell loads the auto-completion scripts
+    /// from (zsh needs it in `fpath`)
+    fn user_config_dir(&self) -> Result<PathBuf> {
+        let (variable, default, dir) = match self.shell {
+            Shell::Bash => (
+                "XDG_DATA_HOME",
+                ".local/share",
+                "bash-completion/completions",
+            ),
+            Shell::Zsh => ("XDG_DATA_HOME", ".local/share", "zsh/site-functions"),
+            Shell::Fish => ("XDG_CONFIG_HOME", ".config", "fish/completions"),
+            Shell::PowerShell => ("XDG_DATA_HOME", ".local/share", "powershell/Modules"),
+            Shell::Elvish => ("XDG_CONFIG_HOME", ".config", "elvish/lib"),
+            shell => return Err(Error::UnsupportedShellError(shell)),
+        };
+        let base_dir = match std::env::var_os(variable) {
+            Some(base_dir) if !base_dir.is_empty() => PathBuf::from(base_dir),
+            _ => std::env::home_dir()
+                .ok_or(Error::NoHomeDirError)?
+                .join(default),
+        };
+        Ok(base_dir.join(dir))
+    }
+    pub fn config_file_name(&self) -> Result<Cow<'static, Path>> {
         let app_name = crate::app_name();
-        match self.shell {
+        Ok(match self.shell {
             Shell::Bash => match app_name {
                 Cow::Borrowed(app_name) => Cow::Borrowed(Path::new(app_name)),
                 Cow::Owned(app_name) => Cow::Owned(PathBuf::from(app_name)),
@@ -301,178 +979,1562 @@ impl ShellOptions {
             Shell::Fish => Cow::Owned(format!("{}.fish", app_name).into()),
             Shell::PowerShell => Cow::Owned(format!("{}.ps1", app_name).into()),
             Shell::Elvish => Cow::Owned(format!("_{}.elv", app_name).into()),
-            shell => panic!("unsupported shell: {}", shell),
+            shell => return Err(Error::UnsupportedShellError(shell)),
+        })
+    }
+    pub fn config_file_path(&self, location: CompletionLocation) -> Result<Cow<'static, Path>> {
+        Ok(Cow::Owned(
+            self.config_dir(location)?.join(self.config_file_name()?),
+        ))
+    }
+    /// where to install the auto-completion script, the directory of the current user with
+    /// `--user` or if the system directory is not writable (the directory is created)
+    pub fn install_location(&self) -> Result<CompletionLocation> {
+        if !self.user {
+            let system_dir = self.config_dir(CompletionLocation::System)?;
+            if is_writable(&system_dir) {
+                return Ok(CompletionLocation::System);
+            }
+            message::diagnostic(
+                Level::Info,
+                format_args!(
+                    "'{}' is not writable, the auto-completion script is installed for the current user.",
+                    system_dir.display()
+                ),
+            );
         }
+        let user_dir = self.config_dir(CompletionLocation::User)?;
+        std::fs::create_dir_all(&user_dir).map_err(Error::io("create", &user_dir))?;
+        Ok(CompletionLocation::User)
     }
-    pub fn config_file_path(&self) -> Result<Cow<'static, Path>> {
-        let config_dir = self.config_dir()?;
-        if !config_dir.exists() {
-            std::fs::create_dir_all(&config_dir).map_err(Error::IOError)?;
+    /// where the auto-completion script is installed (only the directory of the current user
+    /// with `--user`), or the error that it is not installed
+    pub fn installed_location(&self) -> Result<CompletionLocation> {
+        let locations: &[_] = match self.user {
+            true => &[CompletionLocation::User],
+            false => &[CompletionLocation::System, CompletionLocation::User],
+        };
+        for &location in locations {
+            if self.config_file_path(location)?.exists() {
+                return Ok(location);
+            }
         }
-        Ok(Cow::Owned(config_dir.join(self.config_file_name())))
+        Err(Error::NotInstalledError(
+            self.shell,
+            self.config_file_path(locations[0])?.into_owned(),
+        ))
     }
-    pub fn show_installed_info(&self, config_file_path: Cow<Path>) {
+    pub fn show_installed_info(&self, location: CompletionLocation, config_file_path: Cow<Path>) {
         match self.shell {
+            Shell::Zsh if location == CompletionLocation::User => {
+                message::diagnostic(
+                    Level::Info,
+                    format_args!(
+                        "Please add 'fpath=(\"{}\" $fpath)' before 'compinit' in ~/.zshrc, and reset zsh",
+                        config_file_path.parent().unwrap_or(Path::new("")).display()
+                    ),
+                );
+            }
             Shell::PowerShell => {
-                println!(
-                    "{}: Please run 'Import-Module \"{}\"' in powershell",
-                    "INFO".bright_white(),
-                    config_file_path.display()
+                message::diagnostic(
+                    Level::Info,
+                    format_args!(
+                        "Please run 'Import-Module \"{}\"' in powershell",
+                        config_file_path.display()
+                    ),
                 );
             }
             shell @ (Shell::Bash | Shell::Zsh | Shell::Fish) => {
-                println!(
-                    "{}: Please reset {1}, or run 'source \"{2}\"' in {1}",
-                    "INFO".bright_white(),
-                    shell,
-                    config_file_path.display()
+                message::diagnostic(
+                    Level::Info,
+                    format_args!(
+                        "Please reset {0}, or run 'source \"{1}\"' in {0}",
+                        shell,
+                        config_file_path.display()
+                    ),
                 );
             }
             Shell::Elvish => {
-                println!(
-                    "{}: Please reset {1}, or run '{1} \"{2}\"', \n\tFor more information, see https://github.com/zzamboni/elvish-completions.",
-                    "INFO".bright_white(),
-                    Shell::Elvish,
-                    config_file_path.display(),
+                message::diagnostic(
+                    Level::Info,
+                    format_args!(
+                        "Please reset {0}, or run '{0} \"{1}\"', \n\tFor more information, see https://github.com/zzamboni/elvish-completions.",
+                        Shell::Elvish,
+                        config_file_path.display(),
+                    ),
                 );
             }
-            shell => panic!("unsupported shell: {}", shell),
+            // `config_dir` already failed for the other shells
+            _ => {}
         }
     }
 }
 
 impl SubCommand {
-    pub fn run(&self) -> Result<()> {
+    /// the scope of the command, whose defaults are discovered from the current directory
+    /// (see [`ScopeOptions::discover`]), `None` for init, create workspace and remove, which
+    /// always use the current directory (or `-w`), so that remove never deletes a workspace
+    /// or project which was not named
+    pub fn discoverable_scope_mut(&mut self) -> Option<&mut ScopeOptions> {
         match self {
+            SubCommand::AutoComplete { .. }
+            | SubCommand::Init { .. }
+            | SubCommand::Remove { .. } => None,
+            SubCommand::Create { scope, .. } => {
+                (scope.project.is_some() || scope.profile.is_some()).then_some(scope)
+            }
+            SubCommand::Undo { scope }
+            | SubCommand::Redo { scope }
+            | SubCommand::Build { scope, .. }
+            | SubCommand::Clean { scope, .. }
+            | SubCommand::Run { scope, .. }
+            | SubCommand::Gc { scope, .. }
+            | SubCommand::Rebuild { scope, .. }
+            | SubCommand::Set { scope, .. }
+            | SubCommand::Unset { scope, .. }
+            | SubCommand::Get { scope, .. }
+            | SubCommand::Config {
+                sub_command: ConfigSubCommand::Show { scope, .. },
+            } => Some(scope),
+        }
+    }
+    /// run the command, and return the exit code of abuild
+    pub fn run(&self) -> Result<u8> {
+        let result = match self {
             SubCommand::AutoComplete { sub_command } => {
                 let mut buffer = Vec::new();
                 match sub_command {
                     AutoCompleteSubCommand::Output { shell } => {
-                        generate_completion(shell.shell, &crate::app_name(), &mut buffer);
-                        io::stdout().write_all(&buffer).map_err(Error::IOError)?;
+                        generate_completion(*shell, &crate::app_name(), &mut buffer);
+                        match message::format() {
+                            MessageFormat::Human => io::stdout()
+                                .write_all(&buffer)
+                                .map_err(Error::io("write", "<stdout>"))?,
+                            MessageFormat::Json => {
+                                message::message(String::from_utf8_lossy(&buffer))
+                            }
+                        }
                         Ok(())
                     }
                     AutoCompleteSubCommand::Install { shell } => {
-                        let config_file_path = shell.config_file_path()?;
-                        println!(
+                        let location = shell.install_location()?;
+                        let config_file_path = shell.config_file_path(location)?;
+                        message::message(format_args!(
                             "the auto-completion script for {} will be installed in '{}'.",
                             shell.shell,
                             config_file_path.display()
-                        );
+                        ));
                         {
                             let mut config_file = match std::fs::File::create_new(&config_file_path) {
                                 Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
-                                    println!("{}: the auto-completion script for {} is already installed.", "WARNING".bright_yellow(), shell.shell);
-                                    return Ok(());
+                                    message::diagnostic(Level::Warning, format_args!("the auto-completion script for {} is already installed.", shell.shell));
+                                    return Ok(0);
                                 }
                                 res => res,
-                            }.map_err(Error::IOError)?;
+                            }.map_err(Error::io("create", &config_file_path))?;
                             generate_completion(shell.shell, &crate::app_name(), &mut config_file);
                         }
-                        println!(
+                        message::message(format_args!(
                             "the auto-completion script for {} was installed {}.",
                             shell.shell,
                             "successfully".bright_green()
-                        );
+                        ));
 
-                        shell.show_installed_info(config_file_path);
+                        shell.show_installed_info(location, config_file_path);
 
                         Ok(())
                     }
                     AutoCompleteSubCommand::Reinstall { shell } => {
-                        let config_file_path = shell.config_file_path()?;
-                        if config_file_path.exists() {
-                            println!(
-                                "the auto-completion script for {} will be reinstalled in '{}'.",
-                                shell.shell,
-                                config_file_path.display()
-                            );
-                            std::fs::remove_file(&config_file_path).map_err(Error::IOError)?;
-                            {
-                                let mut config_file = std::fs::File::create(&config_file_path)
-                                    .map_err(Error::IOError)?;
-                                generate_completion(
-                                    shell.shell,
-                                    &crate::app_name(),
-                                    &mut config_file,
-                                );
-                            }
-                            println!(
-                                "the auto-completion script for {} was reinstalled {}.",
-                                shell.shell,
-                                "successfully".bright_green()
-                            );
-
-                            shell.show_installed_info(config_file_path);
-                            Ok(())
-                        } else {
-                            println!(
-                                "{}: the auto-completion script for {} was not installed in '{}'.",
-                                "ERROR".bright_red(),
-                                shell.shell,
-                                config_file_path.display()
-                            );
-                            std::process::exit(1);
+                        let location = shell.installed_location()?;
+                        let config_file_path = shell.config_file_path(location)?;
+                        message::message(format_args!(
+                            "the auto-completion script for {} will be reinstalled in '{}'.",
+                            shell.shell,
+                            config_file_path.display()
+                        ));
+                        std::fs::remove_file(&config_file_path)
+                            .map_err(Error::io("remove", &config_file_path))?;
+                        {
+                            let mut config_file = std::fs::File::create(&config_file_path)
+                                .map_err(Error::io("create", &config_file_path))?;
+                            generate_completion(shell.shell, &crate::app_name(), &mut config_file);
                         }
+                        message::message(format_args!(
+                            "the auto-completion script for {} was reinstalled {}.",
+                            shell.shell,
+                            "successfully".bright_green()
+                        ));
+
+                        shell.show_installed_info(location, config_file_path);
+                        Ok(())
                     }
                     AutoCompleteSubCommand::Remove { shell } => {
-                        let config_file_path = shell.config_file_path()?;
-                        if config_file_path.exists() {
-                            println!(
-                                "the auto-completion script for {} will be removed from '{}'.",
-                                shell.shell,
-                                config_file_path.display()
-                            );
-                            std::fs::remove_file(&config_file_path).map_err(Error::IOError)?;
-                            println!(
-                                "the auto-completion script for {} was removed {}.",
-                                shell.shell,
-                                "successfully".bright_green()
-                            );
-                            Ok(())
-                        } else {
-                            println!(
-                                "{}: the auto-completion script for {} was not installed in '{}'.",
-                                "ERROR".bright_red(),
-                                shell.shell,
-                                config_file_path.display()
-                            );
-                            std::process::exit(1);
-                        }
+                        let location = shell.installed_location()?;
+                        let config_file_path = shell.config_file_path(location)?;
+                        message::message(format_args!(
+                            "the auto-completion script for {} will be removed from '{}'.",
+                            shell.shell,
+                            config_file_path.display()
+                        ));
+                        std::fs::remove_file(&config_file_path)
+                            .map_err(Error::io("remove", &config_file_path))?;
+                        message::message(format_args!(
+                            "the auto-completion script for {} was removed {}.",
+                            shell.shell,
+                            "successfully".bright_green()
+                        ));
+                        Ok(())
                     }
                 }
             }
             SubCommand::Init { scope } => {
-                todo!()
+                if scope.project.is_some() {
+                    return Err(Error::UnsupportedError("init project"));
+                }
+                if scope.profile.is_some() {
+                    return Err(Error::UnsupportedError("init profile"));
+                }
+                let workspace_dir = scope.workspace_dir()?;
+                let mut recorder = Recorder::new(
+                    &workspace_dir,
+                    format!("init workspace '{}'", workspace_dir.display()),
+                    None,
+                    None,
+                );
+                recorder
+                    .write(&crate::config::config_file_path(&workspace_dir))
+                    .map_err(Error::JournalError)?;
+                init_workspace(&workspace_dir)?;
+                recorder.finish().map_err(Error::JournalError)?;
+                message::message(format_args!(
+                    "workspace '{}' was initialized {}.",
+                    workspace_dir.display(),
+                    "successfully".bright_green()
+                ));
+                Ok(())
             }
-            SubCommand::Create { scope } => {
-                todo!()
+            SubCommand::Create {
+                language,
+                build_tool,
+                template,
+                profile_options,
+                scope,
+            } => {
+                let workspace_dir = scope.workspace_dir()?;
+                match (&scope.project, &scope.profile) {
+                    (project, Some(profile)) => {
+                        let config_dir = match project {
+                            Some(project) => workspace_dir.join(project),
+                            None => workspace_dir.clone(),
+                        };
+                        let mut recorder = Recorder::new(
+                            &workspace_dir,
+                            format!("create profile '{}'", profile),
+                            project.as_deref(),
+                            Some(profile),
+                        );
+                        recorder
+                            .write(&crate::config::config_file_path(&config_dir))
+                            .map_err(Error::JournalError)?;
+                        create_profile(
+                            &workspace_dir,
+                            project.as_deref(),
+                            profile,
+                            profile_options,
+                        )?;
+                        recorder.finish().map_err(Error::JournalError)?;
+                        message::message(format_args!(
+                            "profile '{}' was created {}.",
+                            profile,
+                            "successfully".bright_green()
+                        ));
+                    }
+                    (Some(project), None) => {
+                        let mut recorder = Recorder::new(
+                            &workspace_dir,
+                            format!("create project '{}'", project),
+                            Some(project),
+                            None,
+                        );
+                        recorder
+                            .write(&crate::config::config_file_path(&workspace_dir))
+                            .map_err(Error::JournalError)?;
+                        let project_dir = create_project(
+                            &workspace_dir,
+                            project,
+                            *language,
+                            *build_tool,
+                            template.as_deref(),
+                            &scope.config,
+                        )?;
+                        recorder
+                            .created(&project_dir)
+                            .map_err(Error::JournalError)?;
+                        recorder.finish().map_err(Error::JournalError)?;
+                        message::message(format_args!(
+                            "project '{}' was created {}.",
+                            project,
+                            "successfully".bright_green()
+                        ));
+                    }
+                    (None, None) => {
+                        let mut recorder = Recorder::new(
+                            &workspace_dir,
+                            format!("create workspace '{}'", workspace_dir.display()),
+                            None,
+                            None,
+                        );
+                        recorder
+                            .write(&crate::config::config_file_path(&workspace_dir))
+                            .map_err(Error::JournalError)?;
+                        init_workspace(&workspace_dir)?;
+                        recorder.finish().map_err(Error::JournalError)?;
+                        message::message(format_args!(
+                            "workspace '{}' was created {}.",
+                            workspace_dir.display(),
+                            "successfully".bright_green()
+                        ));
+                    }
+                }
+                Ok(())
             }
             SubCommand::Remove { scope } => {
-                todo!()
-            }
-            SubCommand::Undo { scope } => {
-                todo!()
-            }
-            SubCommand::Redo { scope } => {
-                todo!()
+                let workspace_dir = scope.workspace_dir()?;
+                let (kind, name) = match (&scope.project, &scope.profile) {
+                    (_, Some(profile)) => ("profile", profile.clone()),
+                    (Some(project), None) => ("project", project.clone()),
+                    (None, None) => ("workspace", workspace_dir.display().to_string()),
+                };
+                let mut recorder = Recorder::new(
+                    &workspace_dir,
+                    format!("remove {} '{}'", kind, name),
+                    scope.project.as_deref(),
+                    scope.profile.as_deref(),
+                );
+                let moved = match (&scope.project, &scope.profile) {
+                    (project, Some(profile)) => {
+                        let config_dir = match project {
+                            Some(project) => workspace_dir.join(project),
+                            None => workspace_dir.clone(),
+                        };
+                        recorder
+                            .write(&crate::config::config_file_path(&config_dir))
+                            .map_err(Error::JournalError)?;
+                        // only the config is recorded, the saved profile is kept by undo
+                        let profile_file =
+                            remove_profile(&workspace_dir, project.as_deref(), profile)?;
+                        message::message(format_args!(
+                            "the profile '{}' was saved in '{}'.",
+                            profile,
+                            profile_file.display()
+                        ));
+                        Vec::new()
+                    }
+                    (Some(project), None) => {
+                        recorder
+                            .write(&crate::config::config_file_path(&workspace_dir))
+                            .map_err(Error::JournalError)?;
+                        remove_project(&workspace_dir, project)?
+                    }
+                    (None, None) => remove_workspace(&workspace_dir)?,
+                };
+                for moved in &moved {
+                    recorder
+                        .moved(&moved.from, &moved.to)
+                        .map_err(Error::JournalError)?;
+                    message::message(format_args!(
+                        "moved '{}' to '{}' ({} files, {}).",
+                        moved.from.display(),
+                        moved.to.display(),
+                        moved.usage.files,
+                        crate::fs::human_size(moved.usage.bytes)
+                    ));
+                }
+                recorder.finish().map_err(Error::JournalError)?;
+                message::message(format_args!(
+                    "{} '{}' was removed {}.",
+                    kind,
+                    name,
+                    "successfully".bright_green()
+                ));
+                if let Some(entry) = moved.first().and_then(|moved| moved.to.parent()) {
+                    message::diagnostic(
+                        Level::Info,
+                        format_args!("the removed files were staged in '{}'.", entry.display()),
+                    );
+                }
+                Ok(())
             }
-            SubCommand::Build { binary, scope } => {
-                todo!()
+            SubCommand::Undo { scope } => replay(scope, true),
+            SubCommand::Redo { scope } => replay(scope, false),
+            SubCommand::Build {
+                binary,
+                target,
+                explain,
+                job_options,
+                scope,
+            } => {
+                let workspace_dir = scope.workspace_dir()?;
+                message::emit(&Event::Start {
+                    operation: Operation::Build,
+                });
+                build(
+                    &workspace_dir,
+                    scope,
+                    binary.as_deref(),
+                    target.as_deref(),
+                    job_options,
+                    *explain,
+                )?;
+                message::emit(&Event::Finish {
+                    operation: Operation::Build,
+                    freed: None,
+                    dry_run: false,
+                });
+                Ok(())
             }
-            SubCommand::Clean { scope } => {
-                todo!()
+            SubCommand::Clean {
+                target,
+                dry_run,
+                scope,
+            } => {
+                let workspace_dir = scope.workspace_dir()?;
+                message::emit(&Event::Start {
+                    operation: Operation::Clean,
+                });
+                let usage = clean(&workspace_dir, scope, target.as_deref(), *dry_run)?;
+                message::emit(&Event::Finish {
+                    operation: Operation::Clean,
+                    freed: Some(usage.bytes),
+                    dry_run: *dry_run,
+                });
+                Ok(())
             }
             SubCommand::Run {
                 binary,
                 args,
+                target,
+                run_options,
+                job_options,
+                scope,
+            } => {
+                let workspace_dir = scope.workspace_dir()?;
+                let artifacts = build(
+                    &workspace_dir,
+                    scope,
+                    binary.as_deref(),
+                    target.as_deref(),
+                    job_options,
+                    false,
+                )?;
+                return run(&artifacts, binary.as_deref(), args, run_options);
+            }
+            SubCommand::Gc {
+                max_size,
+                older_than,
+                dry_run,
                 scope,
             } => {
-                todo!()
+                let workspace_dir = scope.workspace_dir()?;
+                message::emit(&Event::Start {
+                    operation: Operation::Gc,
+                });
+                let usage = gc(&workspace_dir, scope, *max_size, *older_than, *dry_run)?;
+                message::emit(&Event::Finish {
+                    operation: Operation::Gc,
+                    freed: Some(usage.bytes),
+                    dry_run: *dry_run,
+                });
+                Ok(())
+            }
+            SubCommand::Rebuild { .. } => Err(Error::UnsupportedError("rebuild")),
+            SubCommand::Set { key, value, scope } => {
+                let workspace_dir = scope.workspace_dir()?;
+                let set = |value: toml_edit::Value| {
+                    edit_config(&workspace_dir, scope, "set", key, |document, key| {
+                        document.set(key, value)
+                    })
+                };
+                // e.g. `1.0` is a float in toml, but the value of `config.version` is a string
+                let parsed = crate::config::parse_value(value);
+                let (value, (kind, name)) = match set(parsed.clone()) {
+                    Err(Error::ConfigError(crate::config::Error::ParseError { .. }))
+                        if !parsed.is_str() =>
+                    {
+                        let value = toml_edit::Value::from(value.as_str());
+                        (value.clone(), set(value)?)
+                    }
+                    result => (parsed, result?),
+                };
+                message::message(format_args!(
+                    "the {} '{}' {} was set to {}.",
+                    kind, name, key, value
+                ));
+                Ok(())
+            }
+            SubCommand::Unset { key, scope } => {
+                let workspace_dir = scope.workspace_dir()?;
+                let (kind, name) =
+                    edit_config(&workspace_dir, scope, "unset", key, |document, key| {
+                        document.unset(key)
+                    })?;
+                message::message(format_args!("the {} '{}' {} was unset.", kind, name, key));
+                Ok(())
+            }
+            SubCommand::Get { key, scope } => {
+                let workspace_dir = scope.workspace_dir()?;
+                let (path, _, _, full_key) = config_key(&workspace_dir, scope, key)?;
+                let document = crate::config::Document::load(&path).map_err(Error::ConfigError)?;
+                message::emit(&Event::Value {
+                    value: document.get(&full_key).map_err(Error::ConfigError)?,
+                    key: full_key,
+                });
+                Ok(())
+            }
+            SubCommand::Config { sub_command } => match sub_command {
+                ConfigSubCommand::Show { origin, scope } => {
+                    let workspace_dir = scope.workspace_dir()?;
+                    show_config(&workspace_dir, scope, *origin)
+                }
+            },
+        };
+        result.map(|()| 0)
+    }
+}
+
+/// initialize the workspace layout (`.abuild/config.toml`) in an empty directory.
+///
+/// the directory will be created if it does not exist.
+pub fn init_workspace(workspace_dir: &Path) -> Result<()> {
+    make_empty_dir(workspace_dir)?;
+    let name = workspace_dir
+        .canonicalize()
+        .map_err(Error::io("read", workspace_dir))?
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| crate::APP_NAME.to_owned());
+    crate::config::WorkspaceConfig::new(name)
+        .save(workspace_dir)
+        .map_err(Error::ConfigError)
+}
+
+/// create a project named `name` in the workspace and register it in the workspace config.
+///
+/// the project is generated from the user template `template`, or the built-in template of
+/// the language (default: rust) and build tool if no template is given.
+///
+/// the authors of the project are the authors of the layered workspace config, `overrides` are
+/// the `--config` options (see [`crate::config::layer`]).
+pub fn create_project(
+    workspace_dir: &Path,
+    name: &str,
+    language: Option<Language>,
+    build_tool: Option<BuildTool>,
+    template: Option<&str>,
+    overrides: &[(String, String)],
+) -> Result<PathBuf> {
+    check_name(name)?;
+    let workspace_config = load_workspace_config(workspace_dir)?;
+    let authors = Layers::load(workspace_dir, None, overrides)
+        .and_then(|layers| layers.workspace_config())
+        .map_err(Error::ConfigError)?
+        .config
+        .authors;
+    if workspace_config
+        .config
+        .members
+        .iter()
+        .any(|member| member == name)
+    {
+        return Err(Error::AlreadyExistsError(name.to_owned()));
+    }
+    let template = match template {
+        Some(template) => {
+            check_name(template)?;
+            match Template::user(template) {
+                Some(Ok(user_template)) => user_template,
+                Some(Err(e)) if e.kind() == io::ErrorKind::NotFound => {
+                    return Err(Error::TemplateNotFoundError(
+                        template.to_owned(),
+                        crate::template::user_templates_dir(),
+                    ));
+                }
+                Some(Err(e)) => {
+                    let dir = crate::template::user_templates_dir().unwrap_or_default();
+                    return Err(Error::io("read", dir.join(template))(e));
+                }
+                None => return Err(Error::TemplateNotFoundError(template.to_owned(), None)),
+            }
+        }
+        None => {
+            let language = language.unwrap_or(Language::Rust);
+            let build_tool = build_tool.unwrap_or_else(|| language.default_build_tool());
+            if !language.supports(build_tool) {
+                return Err(Error::UnsupportedBuildToolError(language, build_tool));
+            }
+            Template::builtin(language, build_tool)
+                .ok_or(Error::UnsupportedBuildToolError(language, build_tool))?
+        }
+    };
+    let project_dir = workspace_dir.join(name);
+    let existed = project_dir.exists();
+    make_empty_dir(&project_dir)?;
+    // the directory is journaled only when the project was created, so it is cleaned up here
+    let result = (|| {
+        let variables = crate::template::variables(name, &workspace_config.config.name, &authors);
+        template
+            .render(&project_dir, &variables)
+            .map_err(Error::io("render the template into", &project_dir))?;
+        let config_file = crate::config::config_file_path(&project_dir);
+        if !config_file.is_file() {
+            let mut project_config = ProjectConfig::new(name, language.unwrap_or(Language::Rust));
+            project_config.config.authors = authors;
+            project_config
+                .save(&project_dir)
+                .map_err(Error::ConfigError)?;
+        }
+        // the config of a user template keeps its comments
+        let mut document =
+            crate::config::Document::load(&config_file).map_err(Error::ConfigError)?;
+        let project_config = document
+            .check::<ProjectConfig>()
+            .map_err(Error::ConfigError)?;
+        let language = language.unwrap_or(project_config.config.language);
+        let build_tool = build_tool
+            .or(project_config.config.build_tool)
+            .unwrap_or_else(|| language.default_build_tool());
+        if !language.supports(build_tool) {
+            return Err(Error::UnsupportedBuildToolError(language, build_tool));
+        }
+        for (key, value) in [
+            ("config.name", name.to_owned()),
+            ("config.language", language.to_string()),
+            ("config.build-tool", build_tool.to_string()),
+        ] {
+            document
+                .set(key, value.into())
+                .map_err(Error::ConfigError)?;
+        }
+        document.save().map_err(Error::ConfigError)?;
+        std::fs::create_dir_all(project_dir.join("src"))
+            .map_err(Error::io("create", project_dir.join("src")))?;
+        std::fs::create_dir_all(project_dir.join("rc"))
+            .map_err(Error::io("create", project_dir.join("rc")))?;
+
+        edit_workspace_config(workspace_dir, |document| {
+            document.push("config.members", name.into())
+        })?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&project_dir);
+        if existed {
+            let _ = std::fs::create_dir(&project_dir);
+        }
+    }
+    result.map(|_| project_dir)
+}
+
+/// move the project `name` into the trash and unregister it from the workspace config.
+pub fn remove_project(workspace_dir: &Path, name: &str) -> Result<Vec<Moved>> {
+    check_name(name)?;
+    load_workspace_config(workspace_dir)?;
+    let project_dir = workspace_dir.join(name);
+    if !crate::config::config_file_path(&project_dir).is_file() {
+        return Err(Error::NotProjectError(project_dir));
+    }
+    let entry = crate::trash::new_entry(workspace_dir, "project", name)
+        .map_err(Error::io("create the trash of", workspace_dir))?;
+    let moved = crate::trash::stage(&entry, &project_dir, name)
+        .map_err(Error::io("move into the trash", &project_dir))?;
+    edit_workspace_config(workspace_dir, |document| {
+        document.retain("config.members", |member| member.as_str() != Some(name))
+    })?;
+    Ok(vec![moved])
+}
+
+/// move the contents of the workspace (except the trash) into the trash.
+pub fn remove_workspace(workspace_dir: &Path) -> Result<Vec<Moved>> {
+    let workspace_config = load_workspace_config(workspace_dir)?;
+    let config_dir = crate::app_config_dir_in(workspace_dir);
+    let entry = crate::trash::new_entry(workspace_dir, "workspace", &workspace_config.config.name)
+        .map_err(Error::io("create the trash of", workspace_dir))?;
+    let mut moved = Vec::new();
+    for path in
+        crate::fs::sorted_entries(workspace_dir).map_err(Error::io("read", workspace_dir))?
+    {
+        if path == config_dir {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_owned();
+        moved.push(
+            crate::trash::stage(&entry, &path, name)
+                .map_err(Error::io("move into the trash", &path))?,
+        );
+    }
+    let config_file = crate::config::config_file_path(workspace_dir);
+    let config_file_name = config_file
+        .strip_prefix(workspace_dir)
+        .unwrap_or(&config_file)
+        .to_path_buf();
+    moved.push(
+        crate::trash::stage(&entry, &config_file, config_file_name)
+            .map_err(Error::io("move into the trash", &config_file))?,
+    );
+    Ok(moved)
+}
+
+/// add the profile `name` to the project (or workspace) config.
+pub fn create_profile(
+    workspace_dir: &Path,
+    project: Option<&str>,
+    name: &str,
+    options: &ProfileOptions,
+) -> Result<()> {
+    check_name(name)?;
+    let profile = ProfileConfig {
+        inherits: match &options.inherits {
+            Some(inherits) => Some(inherits.clone()),
+            None if ProfileConfig::builtin(name).is_some() => None,
+            None => Some(crate::build::DEFAULT_PROFILE.to_owned()),
+        },
+        opt_level: options.opt_level,
+        debug: options.debug_info,
+        defines: options.defines.clone(),
+        env: options.env.iter().cloned().collect(),
+        ..ProfileConfig::default()
+    };
+    let profile = toml::Table::try_from(&profile).map_err(|e| {
+        Error::ConfigError(crate::config::Error::InvalidValueError {
+            key: format!("profile.{}", name),
+            message: e.to_string(),
+        })
+    })?;
+    let workspace_config = load_workspace_config(workspace_dir)?;
+    let config_dir = match project {
+        Some(project) => {
+            check_name(project)?;
+            let project_dir = workspace_dir.join(project);
+            if !crate::config::config_file_path(&project_dir).is_file() {
+                return Err(Error::NotProjectError(project_dir));
+            }
+            project_dir
+        }
+        None => workspace_dir.to_path_buf(),
+    };
+    let mut document = crate::config::Document::load(&crate::config::config_file_path(&config_dir))
+        .map_err(Error::ConfigError)?;
+    let exists = |profiles: &BTreeMap<String, ProfileConfig>| match profiles.contains_key(name) {
+        true => Err(Error::AlreadyExistsError(name.to_owned())),
+        false => Ok(()),
+    };
+    match project {
+        Some(_) => {
+            exists(
+                &document
+                    .check::<ProjectConfig>()
+                    .map_err(Error::ConfigError)?
+                    .profile,
+            )?;
+            document
+                .set_table(&["profile", name], &profile)
+                .map_err(Error::ConfigError)?;
+            let project_config = document
+                .check::<ProjectConfig>()
+                .map_err(Error::ConfigError)?;
+            Profile::resolve(name, &workspace_config, Some(&project_config))
+                .map_err(Error::ConfigError)?;
+        }
+        None => {
+            exists(&workspace_config.profile)?;
+            document
+                .set_table(&["profile", name], &profile)
+                .map_err(Error::ConfigError)?;
+            let workspace_config = document
+                .check::<WorkspaceConfig>()
+                .map_err(Error::ConfigError)?;
+            Profile::resolve(name, &workspace_config, None).map_err(Error::ConfigError)?;
+        }
+    }
+    document.save().map_err(Error::ConfigError)
+}
+
+/// remove the profile `name` from the project (or workspace) config, the removed profile is
+/// saved in the trash.
+pub fn remove_profile(workspace_dir: &Path, project: Option<&str>, name: &str) -> Result<PathBuf> {
+    check_name(name)?;
+    let removed = match project {
+        Some(project) => {
+            check_name(project)?;
+            let project_dir = workspace_dir.join(project);
+            if !crate::config::config_file_path(&project_dir).is_file() {
+                return Err(Error::NotProjectError(project_dir));
+            }
+            let mut project_config =
+                ProjectConfig::load(&project_dir).map_err(Error::ConfigError)?;
+            let removed = project_config.profile.remove(name).ok_or_else(|| {
+                Error::ConfigError(crate::config::Error::ProfileNotFoundError(name.to_owned()))
+            })?;
+            unset_profile(&project_dir, name)?;
+            removed
+        }
+        None => {
+            let mut workspace_config = load_workspace_config(workspace_dir)?;
+            let removed = workspace_config.profile.remove(name).ok_or_else(|| {
+                Error::ConfigError(crate::config::Error::ProfileNotFoundError(name.to_owned()))
+            })?;
+            unset_profile(workspace_dir, name)?;
+            removed
+        }
+    };
+    let entry = crate::trash::new_entry(workspace_dir, "profile", name)
+        .map_err(Error::io("create the trash of", workspace_dir))?;
+    let profile_file = entry.join(format!("profile.{}.toml", name));
+    crate::config::write(
+        &profile_file,
+        &std::collections::BTreeMap::from([(
+            "profile",
+            std::collections::BTreeMap::from([(name, removed)]),
+        )]),
+    )
+    .map_err(Error::ConfigError)?;
+    Ok(profile_file)
+}
+
+/// edit the workspace config, which keeps its comments
+fn edit_workspace_config(
+    workspace_dir: &Path,
+    edit: impl FnOnce(&mut crate::config::Document) -> crate::config::Result<()>,
+) -> Result<()> {
+    let path = crate::config::config_file_path(workspace_dir);
+    let mut document = crate::config::Document::load(&path).map_err(Error::ConfigError)?;
+    edit(&mut document).map_err(Error::ConfigError)?;
+    document.save().map_err(Error::ConfigError)
+}
+
+/// remove the profile `name` from the config in `config_dir`, which keeps its comments
+fn unset_profile(config_dir: &Path, name: &str) -> Result<()> {
+    let path = crate::config::config_file_path(config_dir);
+    let mut document = crate::config::Document::load(&path).map_err(Error::ConfigError)?;
+    document
+        .unset_segments(&["profile", name])
+        .map_err(Error::ConfigError)?;
+    document.save().map_err(Error::ConfigError)
+}
+
+/// build the project in the scope and its dependencies, or all projects of the workspace, and
+/// return the artifacts of the project (or all projects).
+///
+/// only the binary `binary` of the project is built, all projects are built in the workspace.
+///
+/// the projects which do not depend on each other are built at the same time.
+pub fn build(
+    workspace_dir: &Path,
+    scope: &ScopeOptions,
+    binary: Option<&str>,
+    target: Option<&str>,
+    job_options: &JobOptions,
+    explain: bool,
+) -> Result<Vec<Artifact>> {
+    load_workspace_config(workspace_dir)?;
+    let layers = Layers::load(workspace_dir, None, &scope.config).map_err(Error::ConfigError)?;
+    let workspace_config = layers.workspace_config().map_err(Error::ConfigError)?;
+    let profile = scope
+        .profile
+        .as_deref()
+        .unwrap_or(crate::build::DEFAULT_PROFILE);
+    check_name(profile)?;
+    if let Some(target) = target {
+        check_name(target)?;
+    }
+    let target = target.map(|target| Target::resolve(target, &workspace_config));
+    let roots = match &scope.project {
+        Some(project) => vec![project.clone()],
+        None => workspace_config.config.members.clone(),
+    };
+    let mut projects = BTreeMap::new();
+    for name in workspace_config.config.members.iter().chain(&roots) {
+        check_name(name)?;
+        if !projects.contains_key(name) {
+            let mut project = Project::load(workspace_dir, name).map_err(Error::ConfigError)?;
+            project.config = layers
+                .project_config(project.config)
+                .map_err(Error::ConfigError)?;
+            projects.insert(name.clone(), project);
+        }
+    }
+    let order = crate::build::build_order(&projects, &roots).map_err(Error::BuildError)?;
+    let mut settings = BTreeMap::new();
+    for name in &order {
+        let project = &projects[name];
+        let resolved = Profile::resolve(profile, &workspace_config, Some(&project.config))
+            .map_err(Error::ConfigError)?;
+        settings.insert(name.clone(), resolved);
+    }
+
+    let jobs = job_options.jobs().min(order.len());
+    let order = order
+        .into_iter()
+        .map(|name| {
+            let dependencies = projects[&name].config.config.dependencies.clone();
+            (name, dependencies)
+        })
+        .collect::<Vec<_>>();
+    let schedule = crate::build::schedule(
+        &order,
+        jobs,
+        job_options.keep_going,
+        |name, dependencies: Vec<Dependency>| {
+            let project = &projects[name];
+            let output = match jobs {
+                1 => Output::direct(name),
+                _ => Output::buffered(name),
+            };
+            let result = build_project(
+                &Context {
+                    workspace_dir,
+                    project,
+                    profile,
+                    settings: &settings[&project.name],
+                    target: target.as_ref(),
+                    dependencies: &dependencies,
+                    output: &output,
+                },
+                binary.filter(|_| scope.project.as_ref() == Some(&project.name)),
+                explain,
+            );
+            output.flush();
+            result
+        },
+    );
+    for name in &schedule.skipped {
+        message::diagnostic(
+            Level::Warning,
+            format_args!("the project '{}' was skipped.", name),
+        );
+    }
+    let profile_dir = crate::build::profile_dir(
+        workspace_dir,
+        target.as_ref().map(|target| target.triple.as_str()),
+        profile,
+    );
+    let used = schedule
+        .finished
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    crate::gc::record(workspace_dir, &profile_dir, &used)
+        .map_err(Error::io("record the use of", &profile_dir))?;
+    let mut failed = schedule.failed;
+    match failed.len() {
+        0 => {}
+        1 => return Err(failed.remove(0).1),
+        _ => return Err(Error::ProjectsFailedError(failed)),
+    }
+    let artifacts = order
+        .iter()
+        .filter(|(name, _)| roots.contains(name))
+        .flat_map(|(name, _)| schedule.finished[name].artifacts.clone())
+        .collect::<Vec<_>>();
+    if let Some(binary) = binary
+        && !artifacts.iter().any(|artifact| artifact.name == binary)
+    {
+        return Err(Error::BinaryNotFoundError(Some(binary.to_owned())));
+    }
+    Ok(artifacts)
+}
+
+/// build the project (or only the binary `binary`) if its fingerprint was changed, and print its
+/// artifacts (and why it was built if `explain`) to the output.
+fn build_project(context: &Context, binary: Option<&str>, explain: bool) -> Result<Dependency> {
+    let project = context.project;
+    let build_tool = project.config.build_tool();
+    context.output.emit(Event::Project {
+        project: project.name.clone(),
+        language: project.config.config.language,
+        build_tool,
+        profile: context.profile.to_owned(),
+    });
+    let backend = crate::build::backend(project.config.config.language, build_tool)
+        .map_err(Error::BuildError)?;
+    let toolchain = backend.toolchain(context).map_err(Error::BuildError)?;
+    let fingerprint_file = Fingerprint::path(context);
+    let previous = Fingerprint::load(&fingerprint_file);
+    let mut fingerprint = Fingerprint::compute(context, binary, toolchain, previous.as_ref())
+        .map_err(Error::FingerprintError)?;
+    let changes = fingerprint.changes(previous.as_ref());
+    let artifacts = match previous {
+        Some(previous) if changes.is_empty() => {
+            context.output.emit(Event::Fresh {
+                project: project.name.clone(),
+            });
+            previous.artifacts
+        }
+        _ => {
+            // the reasons are always in the json events
+            let explain = explain
+                || message::format() == MessageFormat::Json
+                || message::verbosity() >= Verbosity::VeryVerbose;
+            context.output.emit(Event::Dirty {
+                project: project.name.clone(),
+                reasons: match explain {
+                    true => changes.iter().map(ToString::to_string).collect(),
+                    false => Vec::new(),
+                },
+            });
+            let artifacts = backend.build(context, binary).map_err(Error::BuildError)?;
+            fingerprint
+                .update(context, &backend.inputs(context))
+                .map_err(Error::FingerprintError)?;
+            fingerprint.artifacts = artifacts.clone();
+            fingerprint
+                .save(&fingerprint_file)
+                .map_err(Error::FingerprintError)?;
+            artifacts
+        }
+    };
+    for artifact in &artifacts {
+        context.output.emit(Event::Artifact {
+            project: project.name.clone(),
+            name: artifact.name.clone(),
+            kind: artifact.kind,
+            path: artifact.path.clone(),
+        });
+    }
+    Ok(Dependency {
+        name: project.name.clone(),
+        project_dir: project.dir.clone(),
+        out_dir: context.build_dir(),
+        artifacts,
+    })
+}
+
+/// remove the build outputs in the scope (or only print them if `dry_run`), and return their
+/// disk usage.
+///
+/// - workspace: `target` (or `target/<triple>`)
+/// - profile: `target/[<triple>/]<profile>`
+/// - project: the build directory, fingerprint and binaries of the project in the profile (or
+///   all profiles)
+pub fn clean(
+    workspace_dir: &Path,
+    scope: &ScopeOptions,
+    target: Option<&str>,
+    dry_run: bool,
+) -> Result<DiskUsage> {
+    load_workspace_config(workspace_dir)?;
+    let mut target_dir = workspace_dir.join(crate::build::TARGET_DIR_NAME);
+    if let Some(target) = target {
+        check_name(target)?;
+        target_dir.push(target);
+    }
+    if let Some(profile) = &scope.profile {
+        check_name(profile)?;
+    }
+    let mut paths = Vec::new();
+    match (&scope.project, &scope.profile) {
+        (None, None) => paths.push(target_dir),
+        (None, Some(profile)) => paths.push(target_dir.join(profile)),
+        (Some(project), profile) => {
+            check_name(project)?;
+            let profile_dirs = match profile {
+                Some(profile) => vec![target_dir.join(profile)],
+                None if target.is_some() => {
+                    crate::gc::profile_dirs(&target_dir).map_err(Error::io("read", &target_dir))?
+                }
+                None => crate::gc::all_profile_dirs(&target_dir)
+                    .map_err(Error::io("read", &target_dir))?,
+            };
+            for profile_dir in profile_dirs {
+                paths.extend(crate::gc::outputs(&profile_dir, project));
+            }
+        }
+    }
+    let total = remove_paths(&paths, dry_run)?;
+    if total.files == 0 {
+        message::message("nothing to clean.");
+    }
+    Ok(total)
+}
+
+/// remove the paths which exist (or only print them if `dry_run`), and return their disk usage
+fn remove_paths(paths: &[PathBuf], dry_run: bool) -> Result<DiskUsage> {
+    let mut total = DiskUsage::default();
+    for path in paths {
+        let Ok(metadata) = std::fs::symlink_metadata(path) else {
+            continue;
+        };
+        let usage = DiskUsage::of(path).map_err(Error::io("measure", path))?;
+        if !dry_run {
+            if metadata.is_dir() {
+                std::fs::remove_dir_all(path).map_err(Error::io("remove", path))?;
+            } else {
+                std::fs::remove_file(path).map_err(Error::io("remove", path))?;
+            }
+        }
+        message::emit(&Event::Removed {
+            path: path.clone(),
+            files: usage.files,
+            bytes: usage.bytes,
+            dry_run,
+        });
+        total += usage;
+    }
+    Ok(total)
+}
+
+/// remove the outputs in the target directory which were not used for `older_than`, and the
+/// least recently used outputs until the target directory is not larger than `max_size` (or
+/// only print them if `dry_run`), and return their disk usage.
+///
+/// only the outputs of the project and profile in the scope are removed.
+pub fn gc(
+    workspace_dir: &Path,
+    scope: &ScopeOptions,
+    max_size: Option<u64>,
+    older_than: Option<Duration>,
+    dry_run: bool,
+) -> Result<DiskUsage> {
+    load_workspace_config(workspace_dir)?;
+    let target_dir = workspace_dir.join(crate::build::TARGET_DIR_NAME);
+    let mut records = crate::gc::load(workspace_dir);
+    let units =
+        crate::gc::units(workspace_dir, &records).map_err(Error::io("read", &target_dir))?;
+    let mut size = match target_dir.exists() {
+        true => {
+            DiskUsage::of(&target_dir)
+                .map_err(Error::io("measure", &target_dir))?
+                .bytes
+        }
+        false => 0,
+    };
+    let cutoff = older_than.map(|older_than| crate::gc::now().saturating_sub(older_than.as_secs()));
+    let mut total = DiskUsage::default();
+    let mut profile_dirs = Vec::new();
+    for unit in units {
+        let in_scope = scope
+            .project
+            .as_ref()
+            .is_none_or(|project| *project == unit.project)
+            && scope.profile.as_ref().is_none_or(|profile| {
+                unit.profile_dir
+                    .file_name()
+                    .is_some_and(|name| name == profile.as_str())
+            });
+        let expired = cutoff.is_some_and(|cutoff| unit.last_used < cutoff);
+        let oversized = max_size.is_some_and(|max_size| size > max_size);
+        if !in_scope || !(expired || oversized) {
+            continue;
+        }
+        total += remove_paths(&unit.paths, dry_run)?;
+        size = size.saturating_sub(unit.usage.bytes);
+        records.remove(&unit.key);
+        if !profile_dirs.contains(&unit.profile_dir) {
+            profile_dirs.push(unit.profile_dir);
+        }
+    }
+    if !dry_run {
+        for profile_dir in &profile_dirs {
+            if !crate::gc::has_outputs(profile_dir) {
+                total += remove_paths(std::slice::from_ref(profile_dir), false)?;
+            }
+        }
+        crate::gc::save(workspace_dir, &records)
+            .map_err(Error::io("write", crate::gc::access_file(workspace_dir)))?;
+    }
+    if total.files == 0 {
+        message::message("nothing to collect.");
+    }
+    Ok(total)
+}
+
+/// run the built binaries (or only the binary `binary`) one by one, and return the first
+/// non-zero exit code.
+///
+/// with `--message-format json`, the output of the binaries is passed as events.
+fn run(
+    artifacts: &[Artifact],
+    binary: Option<&str>,
+    args: &[OsString],
+    run_options: &RunOptions,
+) -> Result<u8> {
+    let binaries = artifacts
+        .iter()
+        .filter(|artifact| artifact.kind == ArtifactKind::Binary)
+        .filter(|artifact| binary.is_none_or(|binary| artifact.name == binary))
+        .collect::<Vec<_>>();
+    if binaries.is_empty() {
+        return Err(Error::BinaryNotFoundError(binary.map(str::to_owned)));
+    }
+    message::emit(&Event::Start {
+        operation: Operation::Run,
+    });
+    let json = message::format() == MessageFormat::Json;
+    let mut exit_code = 0;
+    for artifact in &binaries {
+        if binaries.len() > 1 {
+            message::message(format_args!("binary '{}':", artifact.name));
+        }
+        // a relative path (e.g. `-w .`) would be resolved against `--cwd`
+        let path = artifact
+            .path
+            .canonicalize()
+            .map_err(Error::io("run", &artifact.path))?;
+        let mut command = std::process::Command::new(path);
+        command.args(args).envs(run_options.env.iter().cloned());
+        if let Some(cwd) = &run_options.cwd {
+            command.current_dir(cwd);
+        }
+        if json {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+        let mut child = command.spawn().map_err(Error::io("run", &artifact.path))?;
+        let deadline = run_options.timeout.map(|timeout| Instant::now() + timeout);
+        let status = std::thread::scope(|scope| {
+            if let Some(stdout) = child.stdout.take() {
+                scope.spawn(|| forward_output(&artifact.name, Stream::Stdout, stdout));
             }
-            SubCommand::Rebuild { binary, scope } => {
-                todo!()
+            if let Some(stderr) = child.stderr.take() {
+                scope.spawn(|| forward_output(&artifact.name, Stream::Stderr, stderr));
             }
+            loop {
+                if let Some(status) = child
+                    .try_wait()
+                    .map_err(Error::io("wait for", &artifact.path))?
+                {
+                    break Ok(Some(status));
+                }
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    child.kill().map_err(Error::io("kill", &artifact.path))?;
+                    child
+                        .wait()
+                        .map_err(Error::io("wait for", &artifact.path))?;
+                    break Ok(None);
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        })?;
+        let (code, event) = match status {
+            Some(status) => match status.code() {
+                Some(code) => (
+                    code,
+                    Event::Exited {
+                        binary: artifact.name.clone(),
+                        code: Some(code),
+                        signal: None,
+                        timeout_ms: None,
+                    },
+                ),
+                None => {
+                    let signal = signal(&status);
+                    (
+                        // the exit code of a shell for a program terminated by a signal
+                        signal.map_or(1, |signal| 128 + signal),
+                        Event::Exited {
+                            binary: artifact.name.clone(),
+                            code: None,
+                            signal,
+                            timeout_ms: None,
+                        },
+                    )
+                }
+            },
+            None => (
+                124,
+                Event::Exited {
+                    binary: artifact.name.clone(),
+                    code: None,
+                    signal: None,
+                    timeout_ms: Some(run_options.timeout.unwrap_or_default().as_millis() as u64),
+                },
+            ),
+        };
+        message::emit(&event);
+        if exit_code == 0 {
+            exit_code = code;
         }
     }
+    // the exit code of a process is a byte, a failure must not wrap around to 0 (e.g. 256)
+    Ok(match exit_code {
+        0 => 0,
+        code => code.clamp(1, 255) as u8,
+    })
+}
+
+/// pass each line of the output of a binary as an event
+fn forward_output(binary: &str, stream: Stream, output: impl io::Read) {
+    let mut output = io::BufReader::new(output);
+    let mut line = Vec::new();
+    // programs may write anything, so invalid utf-8 is replaced rather than ending the output
+    while let Ok(1..) = output.read_until(b'\n', &mut line) {
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        message::emit(&Event::Output {
+            project: None,
+            binary: Some(binary.to_owned()),
+            stream,
+            line: String::from_utf8_lossy(text).into_owned(),
+        });
+        line.clear();
+    }
+}
+
+/// the signal which terminated a program
+#[cfg(unix)]
+fn signal(status: &std::process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+/// the signal which terminated a program
+#[cfg(not(unix))]
+fn signal(_status: &std::process::ExitStatus) -> Option<i32> {
+    None
+}
+
+/// undo (or redo) the last operation in the scope.
+fn replay(scope: &ScopeOptions, undo: bool) -> Result<()> {
+    let workspace_dir = scope.workspace_dir()?;
+    let mut journal = Journal::load(&workspace_dir).map_err(Error::JournalError)?;
+    let (from, to) = if undo {
+        (&mut journal.undo, &mut journal.redo)
+    } else {
+        (&mut journal.redo, &mut journal.undo)
+    };
+    let index = Journal::last_in_scope(from, scope.project.as_deref(), scope.profile.as_deref())
+        .ok_or(if undo {
+            Error::NothingToUndoError
+        } else {
+            Error::NothingToRedoError
+        })?;
+    let mut operation = from.remove(index);
+    message::message(format_args!("the last operation is '{}'", operation.name));
+    let messages = if undo {
+        operation.undo(&workspace_dir)
+    } else {
+        operation.redo(&workspace_dir)
+    }
+    .map_err(Error::JournalError)?;
+    for message in messages {
+        message::message(message);
+    }
+    let name = operation.name.clone();
+    to.push(operation);
+    journal.save(&workspace_dir).map_err(Error::JournalError)?;
+    message::message(format_args!(
+        "the operation '{}' was {} {}.",
+        name,
+        if undo { "undone" } else { "redone" },
+        "successfully".bright_green()
+    ));
+    Ok(())
+}
+
+/// whether files can be created in `dir` (which is created if needed)
+fn is_writable(dir: &Path) -> bool {
+    if std::fs::create_dir_all(dir).is_err() {
+        return false;
+    }
+    let probe = dir.join(format!(".{}-{}", crate::app_name(), std::process::id()));
+    match std::fs::File::create_new(&probe) {
+        Ok(_) => std::fs::remove_file(&probe).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// load the workspace config, or fail if `workspace_dir` is not a workspace
+pub fn load_workspace_config(workspace_dir: &Path) -> Result<WorkspaceConfig> {
+    if !crate::config::config_file_path(workspace_dir).is_file() {
+        return Err(Error::NotWorkspaceError(workspace_dir.to_path_buf()));
+    }
+    WorkspaceConfig::load(workspace_dir).map_err(Error::ConfigError)
+}
+
+/// print the effective config of the scope (`key = value`), and the layers the values come
+/// from with `origin`
+pub fn show_config(workspace_dir: &Path, scope: &ScopeOptions, origin: bool) -> Result<()> {
+    load_workspace_config(workspace_dir)?;
+    if let Some(project) = &scope.project {
+        check_name(project)?;
+        let project_dir = workspace_dir.join(project);
+        if !crate::config::config_file_path(&project_dir).is_file() {
+            return Err(Error::NotProjectError(project_dir));
+        }
+    }
+    let layers = Layers::load(workspace_dir, scope.project.as_deref(), &scope.config)
+        .map_err(Error::ConfigError)?;
+    let prefix = scope
+        .profile
+        .as_ref()
+        .map(|profile| format!("profile.{}.", profile));
+    for (key, (value, layer)) in layers.values() {
+        if prefix
+            .as_ref()
+            .is_some_and(|prefix| !key.starts_with(prefix))
+        {
+            continue;
+        }
+        message::emit(&Event::Setting {
+            key,
+            value,
+            origin: origin.then(|| layer.to_string()),
+        });
+    }
+    Ok(())
+}
+
+/// the config file of the scope, the kind and name of the scope, and `key` in the config file
+/// (`profile.<name>.<key>` with `-p`)
+fn config_key(
+    workspace_dir: &Path,
+    scope: &ScopeOptions,
+    key: &str,
+) -> Result<(PathBuf, &'static str, String, String)> {
+    let workspace_config = load_workspace_config(workspace_dir)?;
+    let path = match &scope.project {
+        Some(project) => {
+            check_name(project)?;
+            let project_dir = workspace_dir.join(project);
+            let path = crate::config::config_file_path(&project_dir);
+            if !path.is_file() {
+                return Err(Error::NotProjectError(project_dir));
+            }
+            path
+        }
+        None => crate::config::config_file_path(workspace_dir),
+    };
+    Ok(match (&scope.project, &scope.profile) {
+        (_, Some(profile)) => {
+            check_name(profile)?;
+            (
+                path,
+                "profile",
+                profile.clone(),
+                format!("profile.{}.{}", profile, key),
+            )
+        }
+        (Some(project), None) => (path, "project", project.clone(), key.to_owned()),
+        (None, None) => (
+            path,
+            "workspace",
+            workspace_config.config.name,
+            key.to_owned(),
+        ),
+    })
+}
+
+/// edit the config file of the scope at `key`, the edited config is checked against the config
+/// schema (and its profiles are resolved) before it is saved, the change is recorded in the
+/// journal. returns the kind and name of the scope.
+fn edit_config(
+    workspace_dir: &Path,
+    scope: &ScopeOptions,
+    command: &str,
+    key: &str,
+    edit: impl FnOnce(&mut crate::config::Document, &str) -> crate::config::Result<()>,
+) -> Result<(&'static str, String)> {
+    let (path, kind, name, full_key) = config_key(workspace_dir, scope, key)?;
+    let mut document = crate::config::Document::load(&path).map_err(Error::ConfigError)?;
+    edit(&mut document, &full_key).map_err(Error::ConfigError)?;
+    match &scope.project {
+        Some(_) => {
+            let workspace_config = load_workspace_config(workspace_dir)?;
+            let project_config = document
+                .check::<ProjectConfig>()
+                .map_err(Error::ConfigError)?;
+            for profile in project_config.profile.keys() {
+                Profile::resolve(profile, &workspace_config, Some(&project_config))
+                    .map_err(Error::ConfigError)?;
+            }
+        }
+        None => {
+            let workspace_config = document
+                .check::<WorkspaceConfig>()
+                .map_err(Error::ConfigError)?;
+            for profile in workspace_config.profile.keys() {
+                Profile::resolve(profile, &workspace_config, None).map_err(Error::ConfigError)?;
+            }
+        }
+    }
+    let mut recorder = Recorder::new(
+        workspace_dir,
+        format!("{} {} '{}' {}", command, kind, name, key),
+        scope.project.as_deref(),
+        scope.profile.as_deref(),
+    );
+    recorder.write(&path).map_err(Error::JournalError)?;
+    document.save().map_err(Error::ConfigError)?;
+    recorder.finish().map_err(Error::JournalError)?;
+    Ok((kind, name))
+}
+
+/// check that `name` can be used as a directory name of a project or a profile
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || name
+            .chars()
+            .any(|c| std::path::is_separator(c) || c.is_control() || c == ':')
+    {
+        return Err(Error::InvalidNameError(name.to_owned()));
+    }
+    Ok(())
+}
+
+/// make sure `dir` is an empty directory, the directory will be created if it does not exist
+fn make_empty_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        if std::fs::read_dir(dir)
+            .map_err(Error::io("read", dir))?
+            .next()
+            .is_some()
+        {
+            return Err(Error::NotEmptyDirError(dir.to_path_buf()));
+        }
+    } else {
+        std::fs::create_dir_all(dir).map_err(Error::io("create", dir))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(5 * 60)));
+        assert_eq!(parse_duration("1.5h"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("14d"), Ok(Duration::from_secs(14 * 86400)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::from_secs(2 * 604800)));
+    }
+
+    #[test]
+    fn parse_duration_errors() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("5y").is_err());
+        assert!(parse_duration("1.2.3s").is_err());
+    }
+
+    #[test]
+    fn parse_size_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("512B"), Ok(512));
+        assert_eq!(parse_size("1.5K"), Ok(1536));
+        assert_eq!(parse_size("2MiB"), Ok(2 << 20));
+        assert_eq!(parse_size("20G"), Ok(20 << 30));
+        assert_eq!(parse_size("3gb"), Ok(3 << 30));
+        assert_eq!(parse_size("1T"), Ok(1 << 40));
+    }
+
+    #[test]
+    fn parse_size_errors() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("G").is_err());
+        assert!(parse_size("10X").is_err());
+    }
 }
@@ -0,0 +1,997 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! configuration of workspaces and projects (`.abuild/config.toml`)
+//!
+//! > workspace:
+//! > ```toml
+//! > [config]
+//! > name = "my-workspace"
+//! > version = "0.1.0"
+//! > authors = ["your_name"]
+//! > members = ["my-project"]
+//! >
+//! > [profile.release-lto]
+//! > inherits = "release"
+//! >
+//! > [target.aarch64-unknown-linux-gnu]
+//! > prefix = "aarch64-linux-gnu-"
+//! > ```
+//!
+//! > profiles:
+//! >
+//! > the built-in profiles `debug` (`opt-level = 0`, `debug = true`) and `release`
+//! > (`opt-level = 3`, `debug = false`, `defines = ["NDEBUG"]`) always exist.
+//! > a profile is resolved from (in order, later ones override earlier ones):
+//! >
+//! > 1. the profile it `inherits` (resolved recursively)
+//! > 2. the built-in profile of the same name
+//! > 3. `[profile.<name>]` of the workspace
+//! > 4. `[profile.<name>]` of the project
+//! >
+//! > `defines` are appended to the inherited ones (a duplicate is moved to the end), `env` and
+//! > `cmake` are merged.
+//!
+//! > targets:
+//! >
+//! > the c/c++ compilers of a target are `<prefix>gcc` and `<prefix>g++`, unless `cc` or `cxx`
+//! > is set. the default prefix is the target triple without `unknown` (e.g.
+//! > `aarch64-linux-gnu-` for `aarch64-unknown-linux-gnu`). the `linker` passed to cargo is the
+//! > c compiler if `prefix` or `cc` is set.
+//!
+//! > project:
+//! > ```toml
+//! > [config]
+//! > name = "my-project"
+//! > version = "0.1.0"
+//! > authors = ["your_name"]
+//! > language = "rust"
+//! > build-tool = "cargo"
+//! > dependencies = ["my-library"]
+//! > ```
+//!
+
+pub mod layer;
+
+use crate::lang::{BuildTool, Language};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum Error {
+    IOError(PathBuf, io::Error),
+    ParseError {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    SerializeError(PathBuf, toml::ser::Error),
+    ProfileNotFoundError(String),
+    ProfileCycleError(Vec<String>),
+    InvalidValueError {
+        key: String,
+        message: String,
+    },
+    InvalidKeyError(String),
+    KeyNotFoundError(String),
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::IOError(path, e) => write!(f, "IO error: {}: {}", path.display(), e),
+            Error::ParseError {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Parse error: {}:{}:{}: {}",
+                path.display(),
+                line,
+                column,
+                message
+            ),
+            Error::SerializeError(path, e) => {
+                write!(f, "Serialize error: {}: {}", path.display(), e)
+            }
+            Error::ProfileNotFoundError(name) => write!(f, "The profile '{}' was not found", name),
+            Error::ProfileCycleError(names) => {
+                write!(f, "The profiles inherit in a cycle: {}", names.join(" -> "))
+            }
+            Error::InvalidValueError { key, message } => {
+                write!(f, "Invalid value of '{}': {}", key, message)
+            }
+            Error::InvalidKeyError(key) => write!(f, "Invalid key: '{}'", key),
+            Error::KeyNotFoundError(key) => write!(f, "The key '{}' was not found", key),
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::IOError(_, e) => Some(e),
+            Error::ParseError { .. }
+            | Error::ProfileNotFoundError(_)
+            | Error::ProfileCycleError(_)
+            | Error::InvalidValueError { .. }
+            | Error::InvalidKeyError(_)
+            | Error::KeyNotFoundError(_) => None,
+            Error::SerializeError(_, e) => Some(e),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// `.abuild/config.toml` of a workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WorkspaceConfig {
+    pub config: WorkspaceInfo,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, ProfileConfig>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub target: BTreeMap<String, TargetConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub version: String,
+    /// the authors (default: `config.authors` of the user config, see [`layer`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    /// the member projects (directory names relative to the workspace)
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+/// `.abuild/config.toml` of a project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub config: ProjectInfo,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profile: BTreeMap<String, ProfileConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    pub language: Language,
+    /// the build tool (default: the default build tool of the language)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_tool: Option<BuildTool>,
+    /// the projects of the workspace this project depends on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+/// `[profile.<name>]` of a workspace or project
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProfileConfig {
+    /// the profile to inherit unset fields from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherits: Option<String>,
+    /// the optimization level (0-3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub opt_level: Option<u8>,
+    /// whether to generate debug info
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug: Option<bool>,
+    /// the preprocessor defines (`NAME` or `NAME=VALUE`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub defines: Vec<String>,
+    /// the environment variables passed to the build tool
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// the cache variables passed to cmake (`-D<NAME>=<VALUE>`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cmake: BTreeMap<String, String>,
+}
+
+/// `[target.<triple>]` of a workspace
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TargetConfig {
+    /// the prefix of the c/c++ compilers (e.g. `aarch64-linux-gnu-`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// the c compiler
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cc: Option<String>,
+    /// the c++ compiler
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cxx: Option<String>,
+    /// the linker passed to cargo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub linker: Option<String>,
+}
+
+/// a resolved target
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Target {
+    pub triple: String,
+    pub cc: String,
+    pub cxx: String,
+    pub linker: Option<String>,
+}
+
+/// a resolved profile
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Profile {
+    pub name: String,
+    pub opt_level: u8,
+    pub debug: bool,
+    pub defines: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub cmake: BTreeMap<String, String>,
+}
+
+pub const BUILTIN_PROFILES: [&str; 2] = ["debug", "release"];
+
+impl ProfileConfig {
+    /// the built-in profile `name`
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "debug" => Some(Self {
+                opt_level: Some(0),
+                debug: Some(true),
+                ..Self::default()
+            }),
+            "release" => Some(Self {
+                opt_level: Some(3),
+                debug: Some(false),
+                defines: vec!["NDEBUG".to_owned()],
+                ..Self::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl Profile {
+    /// resolve the profile `name` of the project (or the workspace if `project` is `None`)
+    pub fn resolve(
+        name: &str,
+        workspace: &WorkspaceConfig,
+        project: Option<&ProjectConfig>,
+    ) -> Result<Self> {
+        fn resolve(
+            name: &str,
+            workspace: &WorkspaceConfig,
+            project: Option<&ProjectConfig>,
+            resolving: &mut Vec<String>,
+        ) -> Result<Profile> {
+            if resolving.iter().any(|resolving| resolving == name) {
+                resolving.push(name.to_owned());
+                return Err(Error::ProfileCycleError(resolving.clone()));
+            }
+            let layers = [
+                ProfileConfig::builtin(name),
+                workspace.profile.get(name).cloned(),
+                project.and_then(|project| project.profile.get(name).cloned()),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+            if layers.is_empty() {
+                return Err(Error::ProfileNotFoundError(name.to_owned()));
+            }
+            let mut profile = match layers
+                .iter()
+                .rev()
+                .find_map(|layer| layer.inherits.as_deref())
+            {
+                Some(inherits) => {
+                    resolving.push(name.to_owned());
+                    let profile = resolve(inherits, workspace, project, resolving)?;
+                    resolving.pop();
+                    profile
+                }
+                None => Profile::default(),
+            };
+            profile.name = name.to_owned();
+            for layer in layers {
+                if let Some(opt_level) = layer.opt_level {
+                    if opt_level > 3 {
+                        return Err(Error::InvalidValueError {
+                            key: format!("profile.{}.opt-level", name),
+                            message: format!("expected 0-3, found {}", opt_level),
+                        });
+                    }
+                    profile.opt_level = opt_level;
+                }
+                if let Some(debug) = layer.debug {
+                    profile.debug = debug;
+                }
+                // the env and cli layers are in both the workspace and project profiles, so a
+                // define which is already there is moved to the end (as in the layers)
+                for define in layer.defines {
+                    profile.defines.retain(|previous| *previous != define);
+                    profile.defines.push(define);
+                }
+                profile.env.extend(layer.env);
+                profile.cmake.extend(layer.cmake);
+            }
+            Ok(profile)
+        }
+        resolve(name, workspace, project, &mut Vec::new())
+    }
+    /// the c/c++ compiler flags (e.g. `-O0 -g -DNAME`)
+    pub fn c_flags(&self) -> String {
+        let mut flags = vec![format!("-O{}", self.opt_level)];
+        if self.debug {
+            flags.push("-g".to_owned());
+        }
+        flags.extend(self.defines.iter().map(|define| format!("-D{}", define)));
+        flags.join(" ")
+    }
+}
+
+impl Target {
+    /// resolve the target `triple` of the workspace
+    pub fn resolve(triple: &str, workspace: &WorkspaceConfig) -> Self {
+        let config = workspace.target.get(triple).cloned().unwrap_or_default();
+        let prefix = config.prefix.clone().unwrap_or_else(|| {
+            let components = triple
+                .split('-')
+                .filter(|component| *component != "unknown")
+                .collect::<Vec<_>>();
+            format!("{}-", components.join("-"))
+        });
+        let cc = config
+            .cc
+            .clone()
+            .unwrap_or_else(|| format!("{}gcc", prefix));
+        let linker = match (&config.linker, &config.cc, &config.prefix) {
+            (Some(linker), _, _) => Some(linker.clone()),
+            (None, Some(_), _) | (None, None, Some(_)) => Some(cc.clone()),
+            (None, None, None) => None,
+        };
+        Self {
+            triple: triple.to_owned(),
+            cxx: config.cxx.unwrap_or_else(|| format!("{}g++", prefix)),
+            cc,
+            linker,
+        }
+    }
+}
+
+impl WorkspaceConfig {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            config: WorkspaceInfo {
+                name: name.into(),
+                version: "0.1.0".to_owned(),
+                authors: Vec::new(),
+                members: Vec::new(),
+            },
+            profile: BTreeMap::new(),
+            target: BTreeMap::new(),
+        }
+    }
+    /// load the config of the workspace in `workspace_dir`
+    pub fn load(workspace_dir: &Path) -> Result<Self> {
+        read(&config_file_path(workspace_dir))
+    }
+    /// save the config of the workspace in `workspace_dir`
+    pub fn save(&self, workspace_dir: &Path) -> Result<()> {
+        write(&config_file_path(workspace_dir), self)
+    }
+}
+
+impl ProjectConfig {
+    pub fn new(name: impl Into<String>, language: Language) -> Self {
+        Self {
+            config: ProjectInfo {
+                name: name.into(),
+                version: "0.1.0".to_owned(),
+                authors: Vec::new(),
+                language,
+                build_tool: None,
+                dependencies: Vec::new(),
+            },
+            profile: BTreeMap::new(),
+        }
+    }
+    /// load the config of the project in `project_dir`
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        read(&config_file_path(project_dir))
+    }
+    /// save the config of the project in `project_dir`
+    pub fn save(&self, project_dir: &Path) -> Result<()> {
+        write(&config_file_path(project_dir), self)
+    }
+    /// the build tool of the project
+    pub fn build_tool(&self) -> BuildTool {
+        self.config
+            .build_tool
+            .unwrap_or_else(|| self.config.language.default_build_tool())
+    }
+}
+
+/// a config file which keeps its comments and formatting when it is edited by dotted keys
+/// (e.g. `config.version`, `profile.release.opt-level`)
+#[derive(Debug, Clone)]
+pub struct Document {
+    path: PathBuf,
+    document: toml_edit::DocumentMut,
+}
+
+impl Document {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| Error::IOError(path.to_path_buf(), e))?;
+        let document = content.parse::<toml_edit::DocumentMut>().map_err(|e| {
+            let (line, column) = match e.span() {
+                Some(span) => line_column(&content, span.start),
+                None => (0, 0),
+            };
+            Error::ParseError {
+                path: path.to_path_buf(),
+                line,
+                column,
+                message: e.message().to_owned(),
+            }
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            document,
+        })
+    }
+    pub fn save(&self) -> Result<()> {
+        std::fs::write(&self.path, self.document.to_string())
+            .map_err(|e| Error::IOError(self.path.clone(), e))
+    }
+    /// parse the edited document as `T`, to check it against the config schema
+    pub fn check<T: DeserializeOwned>(&self) -> Result<T> {
+        parse(&self.path, &self.document.to_string())
+    }
+    /// the value of `key`
+    pub fn get(&self, key: &str) -> Result<toml::Value> {
+        let mut value = toml::Value::Table(parse(&self.path, &self.document.to_string())?);
+        for segment in segments(key)? {
+            value = value
+                .as_table_mut()
+                .and_then(|table| table.remove(segment))
+                .ok_or_else(|| Error::KeyNotFoundError(key.to_owned()))?;
+        }
+        Ok(value)
+    }
+    /// set `key` to `value`, the tables of the key are created if needed
+    pub fn set(&mut self, key: &str, value: toml_edit::Value) -> Result<()> {
+        self.set_segments(&segments(key)?, value)
+    }
+    /// set the values of `table` under the key `segments` (e.g. `["profile", "v1.2"]`), the
+    /// table is created if needed (even if `table` is empty)
+    pub fn set_table(&mut self, segments: &[&str], table: &toml::Table) -> Result<()> {
+        if table.is_empty() {
+            let key = segments.join(".");
+            let (last, parents) = segments.split_last().unwrap_or((&"", &[]));
+            let parent = self.table_mut(&key, parents)?;
+            if !parent.contains_key(last) {
+                parent.insert(last, toml_edit::Item::Table(toml_edit::Table::new()));
+            }
+        }
+        for (name, value) in table {
+            let mut segments = segments.to_vec();
+            segments.push(name);
+            match value {
+                toml::Value::Table(table) => self.set_table(&segments, table)?,
+                value => {
+                    let value = value.to_string().parse::<toml_edit::Value>().map_err(|e| {
+                        Error::InvalidValueError {
+                            key: segments.join("."),
+                            message: e.to_string(),
+                        }
+                    })?;
+                    self.set_segments(&segments, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    /// append `value` to the array `key`, the array is created if needed
+    pub fn push(&mut self, key: &str, value: toml_edit::Value) -> Result<()> {
+        let segments = segments(key)?;
+        let (last, parents) = segments.split_last().unwrap_or((&"", &[]));
+        let table = self.table_mut(key, parents)?;
+        match table.get_mut(last) {
+            Some(item) => item
+                .as_array_mut()
+                .ok_or_else(|| Error::InvalidValueError {
+                    key: key.to_owned(),
+                    message: "not an array".to_owned(),
+                })?
+                .push(value),
+            None => {
+                table.insert(
+                    last,
+                    toml_edit::Item::Value(toml_edit::Array::from_iter([value]).into()),
+                );
+            }
+        }
+        Ok(())
+    }
+    /// keep the values of the array `key` for which `keep` returns true
+    pub fn retain(&mut self, key: &str, keep: impl FnMut(&toml_edit::Value) -> bool) -> Result<()> {
+        let segments = segments(key)?;
+        let (last, parents) = segments.split_last().unwrap_or((&"", &[]));
+        if let Some(array) = self
+            .table_mut(key, parents)?
+            .get_mut(last)
+            .and_then(toml_edit::Item::as_array_mut)
+        {
+            array.retain(keep);
+        }
+        Ok(())
+    }
+    /// the table of the key `segments`, the tables are created if needed
+    fn table_mut(&mut self, key: &str, segments: &[&str]) -> Result<&mut dyn toml_edit::TableLike> {
+        let mut table: &mut dyn toml_edit::TableLike = self.document.as_table_mut();
+        let mut inline = false;
+        for segment in segments {
+            if !table.contains_key(segment) {
+                let item = if inline {
+                    toml_edit::Item::Value(toml_edit::InlineTable::new().into())
+                } else {
+                    let mut table = toml_edit::Table::new();
+                    table.set_implicit(true);
+                    toml_edit::Item::Table(table)
+                };
+                table.insert(segment, item);
+            }
+            let item = table.get_mut(segment).unwrap_or_else(|| unreachable!());
+            inline |= item.is_inline_table();
+            table = item
+                .as_table_like_mut()
+                .ok_or_else(|| Error::InvalidValueError {
+                    key: key.to_owned(),
+                    message: format!("'{}' is not a table", segment),
+                })?;
+        }
+        Ok(table)
+    }
+    fn set_segments(&mut self, segments: &[&str], value: toml_edit::Value) -> Result<()> {
+        let key = segments.join(".");
+        let (last, parents) = segments.split_last().unwrap_or((&"", &[]));
+        let table = self.table_mut(&key, parents)?;
+        let mut value = value;
+        match table.get_mut(last) {
+            Some(toml_edit::Item::Value(previous)) => {
+                *value.decor_mut() = previous.decor().clone();
+                *previous = value;
+            }
+            _ => {
+                table.insert(last, toml_edit::Item::Value(value));
+            }
+        }
+        Ok(())
+    }
+    /// remove `key`
+    pub fn unset(&mut self, key: &str) -> Result<()> {
+        self.unset_segments(&segments(key)?)
+    }
+    /// remove the key `segments` (e.g. `["profile", "v1.2"]`)
+    pub fn unset_segments(&mut self, segments: &[&str]) -> Result<()> {
+        let key = segments.join(".");
+        let (last, parents) = segments.split_last().unwrap_or((&"", &[]));
+        let mut table: &mut dyn toml_edit::TableLike = self.document.as_table_mut();
+        for segment in parents {
+            table = table
+                .get_mut(segment)
+                .and_then(toml_edit::Item::as_table_like_mut)
+                .ok_or_else(|| Error::KeyNotFoundError(key.to_owned()))?;
+        }
+        table
+            .remove(last)
+            .ok_or_else(|| Error::KeyNotFoundError(key.to_owned()))?;
+        // remove the tables which were emptied
+        for length in (1..segments.len()).rev() {
+            let (last, parents) = segments[..length]
+                .split_last()
+                .unwrap_or_else(|| unreachable!());
+            let mut table: &mut dyn toml_edit::TableLike = self.document.as_table_mut();
+            for segment in parents {
+                match table
+                    .get_mut(segment)
+                    .and_then(toml_edit::Item::as_table_like_mut)
+                {
+                    Some(child) => table = child,
+                    None => return Ok(()),
+                }
+            }
+            if table
+                .get(last)
+                .and_then(toml_edit::Item::as_table_like)
+                .is_some_and(|child| child.is_empty())
+            {
+                table.remove(last);
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// parse `text` as a toml value (e.g. `1`, `true`, `["a"]`, `"text"`), or a string if it is not
+/// a toml value
+pub fn parse_value(text: &str) -> toml_edit::Value {
+    text.parse::<toml_edit::Value>()
+        .map(|value| undecorated(&value))
+        .unwrap_or_else(|_| text.into())
+}
+
+/// `value` without the whitespace and comments around it
+pub fn undecorated(value: &toml_edit::Value) -> toml_edit::Value {
+    let mut value = value.clone();
+    value.decor_mut().clear();
+    value
+}
+
+/// the segments of the dotted key
+fn segments(key: &str) -> Result<Vec<&str>> {
+    let segments = key.split('.').collect::<Vec<_>>();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(Error::InvalidKeyError(key.to_owned()));
+    }
+    Ok(segments)
+}
+
+/// a workspace found by [`discover`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub workspace_dir: PathBuf,
+    /// the project of the workspace which contains the directory
+    pub project: Option<String>,
+}
+
+/// find the nearest workspace which contains `dir` (or is `dir`) by walking up the parent
+/// directories, and the project of the workspace which contains `dir`, a config file which
+/// cannot be parsed is an error rather than a workspace
+pub fn discover(dir: &Path) -> Result<Option<Location>> {
+    let mut project_dir = None;
+    for ancestor in dir.ancestors() {
+        let path = config_file_path(ancestor);
+        if !path.is_file() {
+            continue;
+        }
+        // only the config of a project has a language
+        let is_project = read::<toml::Table>(&path)?
+            .get("config")
+            .and_then(toml::Value::as_table)
+            .is_some_and(|config| config.contains_key("language"));
+        if is_project {
+            project_dir.get_or_insert(ancestor);
+            continue;
+        }
+        let project = project_dir
+            .filter(|project_dir| project_dir.parent() == Some(ancestor))
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned());
+        return Ok(Some(Location {
+            workspace_dir: ancestor.to_path_buf(),
+            project,
+        }));
+    }
+    Ok(None)
+}
+
+/// the path of `.abuild/config.toml` in `dir`
+pub fn config_file_path(dir: &Path) -> PathBuf {
+    crate::app_config_dir_in(dir).join(crate::CONFIG_FILE_NAME)
+}
+
+/// parse `content` as the config file in `path`
+pub fn parse<T: DeserializeOwned>(path: &Path, content: &str) -> Result<T> {
+    toml::from_str(content).map_err(|e| {
+        let (line, column) = match e.span() {
+            Some(span) => line_column(content, span.start),
+            None => (0, 0),
+        };
+        Error::ParseError {
+            path: path.to_path_buf(),
+            line,
+            column,
+            message: e.message().to_owned(),
+        }
+    })
+}
+
+/// read and parse the config file in `path`
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| Error::IOError(path.to_path_buf(), e))?;
+    parse(path, &content)
+}
+
+/// serialize and write the config file in `path`, the parent directory will be created if needed
+pub fn write<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let content =
+        toml::to_string_pretty(value).map_err(|e| Error::SerializeError(path.to_path_buf(), e))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| Error::IOError(parent.to_path_buf(), e))?;
+    }
+    std::fs::write(path, content).map_err(|e| Error::IOError(path.to_path_buf(), e))
+}
+
+/// 1-based line and column of the byte `offset` in `content`
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// an empty directory for the test `name`
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("abuild-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = temp_dir("config");
+        let mut workspace = WorkspaceConfig::new("ws");
+        workspace.config.members.push("app".to_owned());
+        workspace.save(&dir).unwrap();
+        assert_eq!(WorkspaceConfig::load(&dir).unwrap(), workspace);
+        let project_dir = dir.join("app");
+        let mut project = ProjectConfig::new("app", Language::Rust);
+        project.config.dependencies.push("lib".to_owned());
+        project.save(&project_dir).unwrap();
+        assert_eq!(ProjectConfig::load(&project_dir).unwrap(), project);
+        assert_eq!(project.build_tool(), BuildTool::Cargo);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parse_errors() {
+        let path = Path::new("config.toml");
+        let content = "[config]\nname = \"ws\"\nversion = \"0.1.0\"\nmember = []\n";
+        assert!(matches!(
+            parse::<WorkspaceConfig>(path, content),
+            Err(Error::ParseError {
+                line: 4,
+                column: 1,
+                ..
+            })
+        ));
+        let content = "[config]\nname = \"app\"\nversion = \"0.1.0\"\nlanguage = \"cobol\"\n";
+        assert!(matches!(
+            parse::<ProjectConfig>(path, content),
+            Err(Error::ParseError { line: 4, .. })
+        ));
+    }
+
+    fn profile(config: &str) -> ProfileConfig {
+        toml::from_str(config).unwrap()
+    }
+
+    #[test]
+    fn resolve_builtin_profiles() {
+        let workspace = WorkspaceConfig::new("ws");
+        let debug = Profile::resolve("debug", &workspace, None).unwrap();
+        assert_eq!((debug.opt_level, debug.debug), (0, true));
+        let release = Profile::resolve("release", &workspace, None).unwrap();
+        assert_eq!((release.opt_level, release.debug), (3, false));
+        assert_eq!(release.defines, ["NDEBUG"]);
+    }
+
+    #[test]
+    fn resolve_layers_and_inherits() {
+        let mut workspace = WorkspaceConfig::new("ws");
+        workspace.profile.insert(
+            "bench".to_owned(),
+            profile("inherits = \"release\"\nopt-level = 2\ndefines = [\"W\"]"),
+        );
+        let mut project = ProjectConfig::new("app", Language::C);
+        project.profile.insert(
+            "bench".to_owned(),
+            profile("debug = true\ndefines = [\"P\", \"W\"]\nenv = { A = \"1\" }"),
+        );
+        let bench = Profile::resolve("bench", &workspace, Some(&project)).unwrap();
+        assert_eq!(bench.name, "bench");
+        assert_eq!((bench.opt_level, bench.debug), (2, true));
+        assert_eq!(bench.defines, ["NDEBUG", "P", "W"]);
+        assert_eq!(
+            bench.env,
+            BTreeMap::from([("A".to_owned(), "1".to_owned())])
+        );
+        let bench = Profile::resolve("bench", &workspace, None).unwrap();
+        assert_eq!((bench.opt_level, bench.debug), (2, false));
+        assert_eq!(bench.defines, ["NDEBUG", "W"]);
+    }
+
+    #[test]
+    fn resolve_errors() {
+        let mut workspace = WorkspaceConfig::new("ws");
+        workspace
+            .profile
+            .insert("a".to_owned(), profile("inherits = \"b\""));
+        workspace
+            .profile
+            .insert("b".to_owned(), profile("inherits = \"a\""));
+        workspace
+            .profile
+            .insert("fast".to_owned(), profile("opt-level = 4"));
+        assert!(matches!(
+            Profile::resolve("a", &workspace, None),
+            Err(Error::ProfileCycleError(cycle)) if cycle == ["a", "b", "a"]
+        ));
+        assert!(matches!(
+            Profile::resolve("missing", &workspace, None),
+            Err(Error::ProfileNotFoundError(name)) if name == "missing"
+        ));
+        assert!(matches!(
+            Profile::resolve("fast", &workspace, None),
+            Err(Error::InvalidValueError { key, .. }) if key == "profile.fast.opt-level"
+        ));
+    }
+
+    #[test]
+    fn resolve_targets() {
+        let mut workspace = WorkspaceConfig::new("ws");
+        let target = Target::resolve("aarch64-unknown-linux-gnu", &workspace);
+        assert_eq!(target.cc, "aarch64-linux-gnu-gcc");
+        assert_eq!(target.cxx, "aarch64-linux-gnu-g++");
+        assert_eq!(target.linker, None);
+
+        let config = toml::from_str("prefix = \"arm-none-eabi-\"").unwrap();
+        workspace
+            .target
+            .insert("thumbv7em-none-eabihf".to_owned(), config);
+        let target = Target::resolve("thumbv7em-none-eabihf", &workspace);
+        assert_eq!(target.cc, "arm-none-eabi-gcc");
+        assert_eq!(target.linker.as_deref(), Some("arm-none-eabi-gcc"));
+
+        let config = toml::from_str("cc = \"clang --target=x86_64-w64-mingw32\"").unwrap();
+        workspace
+            .target
+            .insert("x86_64-pc-windows-gnu".to_owned(), config);
+        let target = Target::resolve("x86_64-pc-windows-gnu", &workspace);
+        assert_eq!(target.cc, "clang --target=x86_64-w64-mingw32");
+        assert_eq!(target.cxx, "x86_64-pc-windows-gnu-g++");
+        assert_eq!(target.linker, Some(target.cc.clone()));
+    }
+
+    fn edit(name: &str, content: &str, edit: impl FnOnce(&mut Document)) -> String {
+        let dir = temp_dir(name);
+        let path = dir.join(crate::CONFIG_FILE_NAME);
+        std::fs::write(&path, content).unwrap();
+        let mut document = Document::load(&path).unwrap();
+        edit(&mut document);
+        document.save().unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        content
+    }
+
+    const CONFIG: &str = r#"# the workspace
+[config]
+name = "ws" # the name
+version = "0.1.0"
+members = []
+"#;
+
+    #[test]
+    fn document_set_keeps_formatting() {
+        let content = edit("document-set", CONFIG, |document| {
+            document.set("config.version", "0.2.0".into()).unwrap();
+        });
+        assert_eq!(content, CONFIG.replace("0.1.0", "0.2.0"));
+    }
+
+    #[test]
+    fn document_set_creates_tables() {
+        let content = edit("document-set-tables", CONFIG, |document| {
+            document.set("profile.release.opt-level", 2.into()).unwrap();
+        });
+        assert!(content.starts_with(CONFIG));
+        let table = parse::<toml::Table>(Path::new(""), &content).unwrap();
+        assert_eq!(
+            table["profile"]["release"]["opt-level"].as_integer(),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn document_unset_prunes_empty_tables() {
+        let content = edit("document-unset", CONFIG, |document| {
+            document.set("profile.release.opt-level", 2.into()).unwrap();
+            document.unset("profile.release.opt-level").unwrap();
+        });
+        assert_eq!(content, CONFIG);
+        let content = edit("document-unset-key", CONFIG, |document| {
+            document.unset("config.members").unwrap();
+        });
+        assert_eq!(content, CONFIG.replace("members = []\n", ""));
+    }
+
+    #[test]
+    fn document_invalid_keys() {
+        edit("document-invalid", CONFIG, |document| {
+            assert!(matches!(
+                document.set("config..name", 1.into()),
+                Err(Error::InvalidKeyError(_))
+            ));
+            assert!(document.unset("config.missing").is_err());
+        });
+    }
+
+    #[test]
+    fn discover_workspace_and_project() {
+        let workspace_dir = temp_dir("discover");
+        WorkspaceConfig::new("ws").save(&workspace_dir).unwrap();
+        let project_dir = workspace_dir.join("app");
+        ProjectConfig::new("app", Language::C)
+            .save(&project_dir)
+            .unwrap();
+        let nested_dir = workspace_dir.join("group").join("lib");
+        ProjectConfig::new("lib", Language::C)
+            .save(&nested_dir)
+            .unwrap();
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        let location = |workspace_dir: &Path, project: Option<&str>| Location {
+            workspace_dir: workspace_dir.to_path_buf(),
+            project: project.map(str::to_owned),
+        };
+        assert_eq!(
+            discover(&project_dir.join("src")).unwrap(),
+            Some(location(&workspace_dir, Some("app")))
+        );
+        assert_eq!(
+            discover(&project_dir).unwrap(),
+            Some(location(&workspace_dir, Some("app")))
+        );
+        assert_eq!(
+            discover(&workspace_dir).unwrap(),
+            Some(location(&workspace_dir, None))
+        );
+        // only the direct members of the workspace are projects
+        assert_eq!(
+            discover(&nested_dir).unwrap(),
+            Some(location(&workspace_dir, None))
+        );
+        // a broken project is not the workspace
+        std::fs::write(config_file_path(&project_dir), "[config\n").unwrap();
+        assert!(matches!(
+            discover(&project_dir.join("src")),
+            Err(Error::ParseError { .. })
+        ));
+        std::fs::remove_dir_all(&workspace_dir).unwrap();
+    }
+}
@@ -0,0 +1,373 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! layered configuration
+//!
+//! > the config is resolved from the layers (in order, later ones override earlier ones):
+//! >
+//! > 1. user: `<user config dir>/config.toml` (e.g. `~/.config/abuild/config.toml`)
+//! > 2. workspace: `.abuild/config.toml` of the workspace
+//! > 3. project: `.abuild/config.toml` of the project
+//! > 4. env: the environment variables `ABUILD_<KEY>`, the segments of the dotted key are
+//! >    separated by `__` (e.g. `ABUILD_PROFILE__RELEASE__OPT_LEVEL=2`)
+//! > 5. cli: the `--config <KEY>=<VALUE>` options (e.g. `--config profile.release.opt-level=2`)
+//!
+//! > the user, env and cli layers can only set the shared keys: `config.authors`,
+//! > `[profile.<name>]` and `[target.<triple>]`, e.g.
+//! > ```toml
+//! > [config]
+//! > authors = ["your_name"]
+//! >
+//! > [target.aarch64-unknown-linux-gnu]
+//! > cc = "aarch64-linux-gnu-gcc-13"
+//! > ```
+//!
+//! + ![note] the names of the environment variables are lowercased, and `_` is replaced with
+//!   `-` (e.g. `OPT_LEVEL` is `opt-level`), except:
+//!   + the names of the profiles and targets in the config files (and the built-in profiles),
+//!     which are matched as they are written (e.g. `ABUILD_PROFILE__RELEASE_LTO__DEBUG=true` sets
+//!     `profile.release_lto.debug` if the profile `release_lto` exists, or
+//!     `ABUILD_TARGET__X86_64_UNKNOWN_LINUX_GNU__CC` if the target is configured).
+//!   + the keys of `env` and `cmake` of a profile, which are kept as they are (e.g.
+//!     `ABUILD_PROFILE__DEBUG__CMAKE__CMAKE_EXPORT_COMPILE_COMMANDS=ON`).
+//!
+//!   other names with `_` can be set with `--config`.
+//! + ![note] the values of the env and cli layers are toml values (e.g. `2`, `'["a", "b"]'`),
+//!   or strings if they are not.
+//! + ![note] the env and cli layers override the profiles of the project too.
+//! + ![note] the tables are merged, and the `defines` of a profile are appended (as the workspace
+//!   and project profiles are resolved), other values (and arrays) are replaced.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::config::{
+    BUILTIN_PROFILES, Error, ProfileConfig, ProjectConfig, Result, TargetConfig, WorkspaceConfig,
+    config_file_path, parse,
+};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// the prefix of the environment variables of the env layer
+pub const ENV_PREFIX: &str = "ABUILD_";
+/// the separator of the segments of the keys in the environment variables
+pub const ENV_SEPARATOR: &str = "__";
+
+/// the sections of the environment variables of the env layer
+const SHARED_SECTIONS: [&str; 3] = ["CONFIG", "PROFILE", "TARGET"];
+
+/// where a layer comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    User(PathBuf),
+    Workspace(PathBuf),
+    Project(PathBuf),
+    Env(String),
+    Cli(String),
+}
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::User(path) => write!(f, "user '{}'", path.display()),
+            Origin::Workspace(path) => write!(f, "workspace '{}'", path.display()),
+            Origin::Project(path) => write!(f, "project '{}'", path.display()),
+            Origin::Env(name) => write!(f, "env '{}'", name),
+            Origin::Cli(key) => write!(f, "cli '--config {}'", key),
+        }
+    }
+}
+
+/// a layer of the config
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub origin: Origin,
+    pub table: toml::Table,
+}
+
+/// the layers of the config, the earlier ones first
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Layers {
+    pub layers: Vec<Layer>,
+}
+
+impl Layers {
+    /// the layers of the workspace (and the project), `overrides` are the `--config` options
+    pub fn load(
+        workspace_dir: &Path,
+        project: Option<&str>,
+        overrides: &[(String, String)],
+    ) -> Result<Self> {
+        let mut layers = Vec::new();
+        if let Some(path) = user_config_file()
+            && path.is_file()
+        {
+            let table = read::<toml::Table>(&path)?;
+            let origin = Origin::User(path);
+            check_shared(&table, &origin)?;
+            layers.push(Layer { origin, table });
+        }
+        let path = config_file_path(workspace_dir);
+        layers.push(Layer {
+            table: read::<WorkspaceConfig>(&path)?,
+            origin: Origin::Workspace(path),
+        });
+        if let Some(project) = project {
+            let path = config_file_path(&workspace_dir.join(project));
+            layers.push(Layer {
+                table: read::<ProjectConfig>(&path)?,
+                origin: Origin::Project(path),
+            });
+        }
+        // other variables (e.g. `ABUILD_DEPS`, which is passed to the build tools) are not config
+        let mut variables = std::env::vars()
+            .filter(|(name, _)| {
+                name.strip_prefix(ENV_PREFIX)
+                    .and_then(|key| key.split_once(ENV_SEPARATOR))
+                    .is_some_and(|(section, _)| SHARED_SECTIONS.contains(&section))
+            })
+            .collect::<Vec<_>>();
+        variables.sort();
+        for (name, value) in variables {
+            let key = env_key(&name[ENV_PREFIX.len()..], &layers);
+            layers.push(override_layer(Origin::Env(name), &key, &value)?);
+        }
+        for (key, value) in overrides {
+            layers.push(override_layer(
+                Origin::Cli(format!("{}={}", key, value)),
+                key,
+                value,
+            )?);
+        }
+        Ok(Self { layers })
+    }
+    /// the effective config of the workspace (all layers except the project layer)
+    pub fn workspace_config(&self) -> Result<WorkspaceConfig> {
+        let mut table = toml::Table::new();
+        for layer in &self.layers {
+            if !matches!(layer.origin, Origin::Project(_)) {
+                merge(&mut table, &layer.table, &[]);
+            }
+        }
+        table.try_into().map_err(|e| Error::InvalidValueError {
+            key: "config".to_owned(),
+            message: e.to_string(),
+        })
+    }
+    /// the effective config of a project, the env and cli layers are applied to `config`
+    pub fn project_config(&self, config: ProjectConfig) -> Result<ProjectConfig> {
+        let overrides = self
+            .layers
+            .iter()
+            .filter(|layer| matches!(layer.origin, Origin::Env(_) | Origin::Cli(_)))
+            .collect::<Vec<_>>();
+        if overrides.is_empty() {
+            return Ok(config);
+        }
+        let mut table = toml::Table::try_from(&config).map_err(|e| Error::InvalidValueError {
+            key: "config".to_owned(),
+            message: e.to_string(),
+        })?;
+        for layer in overrides {
+            let mut layer = layer.table.clone();
+            // the targets are only in the workspace config
+            layer.remove("target");
+            merge(&mut table, &layer, &[]);
+        }
+        table.try_into().map_err(|e| Error::InvalidValueError {
+            key: "config".to_owned(),
+            message: e.to_string(),
+        })
+    }
+    /// the effective values by their dotted keys, and the layers they come from
+    pub fn values(&self) -> BTreeMap<String, (toml::Value, &Origin)> {
+        fn visit<'a>(
+            path: &[&str],
+            table: &toml::Table,
+            origin: &'a Origin,
+            values: &mut BTreeMap<String, (toml::Value, &'a Origin)>,
+        ) {
+            for (key, value) in table {
+                let path = [path, &[key.as_str()]].concat();
+                match (value, values.get_mut(&path.join("."))) {
+                    (toml::Value::Table(table), _) => visit(&path, table, origin, values),
+                    (toml::Value::Array(other), Some((toml::Value::Array(array), previous)))
+                        if is_appended(&path) =>
+                    {
+                        append(array, other);
+                        *previous = origin;
+                    }
+                    (value, _) => {
+                        values.insert(path.join("."), (value.clone(), origin));
+                    }
+                }
+            }
+        }
+        let mut values = BTreeMap::new();
+        for layer in &self.layers {
+            visit(&[], &layer.table, &layer.origin, &mut values);
+        }
+        values
+    }
+}
+
+/// the user config file (`<user config dir>/config.toml`)
+pub fn user_config_file() -> Option<PathBuf> {
+    Some(crate::user_config_dir()?.join(crate::CONFIG_FILE_NAME))
+}
+
+/// merge `other` into `table` (at the segments `path`), the tables are merged recursively, the
+/// appended arrays are appended, other values are replaced
+fn merge(table: &mut toml::Table, other: &toml::Table, path: &[&str]) {
+    for (key, value) in other {
+        let path = [path, &[key.as_str()]].concat();
+        match (table.get_mut(key), value) {
+            (Some(toml::Value::Table(table)), toml::Value::Table(other)) => {
+                merge(table, other, &path)
+            }
+            (Some(toml::Value::Array(array)), toml::Value::Array(other)) if is_appended(&path) => {
+                append(array, other)
+            }
+            _ => {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// whether the array at the segments `path` is appended by the later layers, as the defines of
+/// the profiles are in [`crate::config::Profile::resolve`]
+fn is_appended(path: &[&str]) -> bool {
+    matches!(path, ["profile", _, "defines"])
+}
+
+/// append the values of `other` to `array`, a value which is already in `array` is moved to the end
+fn append(array: &mut Vec<toml::Value>, other: &[toml::Value]) {
+    for value in other {
+        array.retain(|previous| previous != value);
+        array.push(value.clone());
+    }
+}
+
+/// the dotted key of the environment variable `name` (without the prefix), the names of the
+/// profiles and targets in `layers` are matched as they are written
+fn env_key(name: &str, layers: &[Layer]) -> String {
+    let mut key = Vec::<String>::new();
+    for segment in name.split(ENV_SEPARATOR) {
+        let path = key.iter().map(String::as_str).collect::<Vec<_>>();
+        let literal = match path.as_slice() {
+            // the names of environment variables and cmake cache variables
+            ["profile", _, "env" | "cmake"] => Some(segment.to_owned()),
+            [section @ ("profile" | "target")] => {
+                let builtin = match *section {
+                    "profile" => &BUILTIN_PROFILES[..],
+                    _ => &[],
+                };
+                layers
+                    .iter()
+                    .filter_map(|layer| layer.table.get(*section)?.as_table())
+                    .flat_map(|table| table.keys().map(String::as_str))
+                    .chain(builtin.iter().copied())
+                    .find(|name| name.to_uppercase().replace('-', "_") == segment)
+                    .map(str::to_owned)
+            }
+            _ => None,
+        };
+        key.push(literal.unwrap_or_else(|| segment.to_lowercase().replace('_', "-")));
+    }
+    key.join(".")
+}
+
+/// the layer which sets the dotted `key` to `value` (a toml value, or a string)
+fn override_layer(origin: Origin, key: &str, value: &str) -> Result<Layer> {
+    let value = toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_owned()));
+    let segments = key.split('.').collect::<Vec<_>>();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(Error::InvalidKeyError(key.to_owned()));
+    }
+    let mut table = toml::Table::new();
+    let mut value = value;
+    for segment in segments.iter().skip(1).rev() {
+        let mut parent = toml::Table::new();
+        parent.insert((*segment).to_owned(), value);
+        value = toml::Value::Table(parent);
+    }
+    table.insert(segments[0].to_owned(), value);
+    check_shared(&table, &origin)?;
+    Ok(Layer { origin, table })
+}
+
+/// check that the layer only sets the keys which the user, env and cli layers can set:
+/// `config.authors`, `[profile.<name>]` and `[target.<triple>]`
+fn check_shared(table: &toml::Table, origin: &Origin) -> Result<()> {
+    let error = |message: String| Error::InvalidValueError {
+        key: origin.to_string(),
+        message,
+    };
+    for (key, value) in table {
+        match (key.as_str(), value) {
+            ("config", toml::Value::Table(config)) => {
+                for (key, value) in config {
+                    match key.as_str() {
+                        "authors" => check::<Vec<String>>(value, origin)?,
+                        _ => {
+                            return Err(error(format!(
+                                "unknown field `config.{}`, expected `config.authors`",
+                                key
+                            )));
+                        }
+                    }
+                }
+            }
+            ("config", _) => return Err(error("invalid type, expected table `config`".to_owned())),
+            ("profile", value) => check::<BTreeMap<String, ProfileConfig>>(value, origin)?,
+            ("target", value) => check::<BTreeMap<String, TargetConfig>>(value, origin)?,
+            _ => {
+                return Err(error(format!(
+                    "unknown field `{}`, expected one of `config`, `profile`, `target`",
+                    key
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// check that the value matches the schema `T`
+fn check<T: DeserializeOwned>(value: &toml::Value, origin: &Origin) -> Result<()> {
+    value
+        .clone()
+        .try_into::<T>()
+        .map(|_| ())
+        .map_err(|e| Error::InvalidValueError {
+            key: origin.to_string(),
+            message: e.message().to_owned(),
+        })
+}
+
+/// read the config file as a layer, which is checked against the schema `T`
+fn read<T: DeserializeOwned>(path: &Path) -> Result<toml::Table> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| Error::IOError(path.to_path_buf(), e))?;
+    let table: toml::Table = parse(path, &content)?;
+    // the schema is parsed from the content again only for the location of its error
+    if toml::Value::Table(table.clone()).try_into::<T>().is_err() {
+        parse::<T>(path, &content)?;
+    }
+    Ok(table)
+}
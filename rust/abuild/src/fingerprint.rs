@@ -0,0 +1,416 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! fingerprints of the built projects (`target/<profile>/fingerprint/<project>.json`), a
+//! project is not built again if its fingerprint was not changed.
+//!
+//! > the fingerprint of a project:
+//! >
+//! > - `toolchain`: the versions of the build tool and compilers
+//! > - `binary`: the binary which was built (`-b`), or all binaries
+//! > - `config`: the config of the project
+//! > - `profile`: the resolved profile
+//! > - `env`: the environment variables read by the build tools and compilers (e.g. `CFLAGS`,
+//! >   `RUSTFLAGS`)
+//! > - `dependencies`: the hashes of the artifacts and files of the dependencies
+//! > - `files`: the hashes of the files in the project directory
+//! > - `inputs`: the hashes of the files out of the project directory which were read by the last
+//! >   build (e.g. cargo path dependencies, see [`crate::build::Backend::inputs`])
+//! > - `artifacts`: the built binaries and libraries
+//!
+//! + ![note] the files are compared by their content hashes, the hash of a file is only computed
+//!   again if its size or modification time changed.
+//! + ![note] hidden directories (e.g. `.abuild`, `.git`) and `target` are not fingerprinted.
+//! + ![note] the fingerprint is taken before the build, so a file which was changed during the
+//!   build makes the project dirty, the files generated by the build tool in the project
+//!   directory are added after the build, so they do not make the project dirty.
+//! + ![note] the project is built again if any artifact was removed.
+//! + ![note] the other files out of the project directory (e.g. system headers) and the
+//!   environment variables read by build scripts are not fingerprinted.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::build::{Artifact, Context, Dependency};
+use crate::config::{Profile, ProjectConfig};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub const FINGERPRINT_DIR_NAME: &str = "fingerprint";
+/// the environment variables which are read by the build tools and compilers, but are not a part
+/// of the profile
+const ENV_NAMES: &[&str] = &[
+    "CC",
+    "CXX",
+    "CFLAGS",
+    "CXXFLAGS",
+    "CPPFLAGS",
+    "LDFLAGS",
+    "LDLIBS",
+    "RUSTC",
+    "RUSTC_WRAPPER",
+    "RUSTFLAGS",
+    "CARGO_ENCODED_RUSTFLAGS",
+    "RUSTUP_TOOLCHAIN",
+];
+
+#[derive(Debug)]
+pub enum Error {
+    IOError(PathBuf, io::Error),
+    SerializeError(PathBuf, serde_json::Error),
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::IOError(path, e) => write!(f, "IO error: {}: {}", path.display(), e),
+            Error::SerializeError(path, e) => {
+                write!(f, "Serialize error: {}: {}", path.display(), e)
+            }
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::IOError(_, e) => Some(e),
+            Error::SerializeError(_, e) => Some(e),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// the fingerprint of a project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub toolchain: Vec<String>,
+    pub binary: Option<String>,
+    pub config: ProjectConfig,
+    pub profile: Profile,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub dependencies: BTreeMap<String, String>,
+    pub files: BTreeMap<String, File>,
+    #[serde(default)]
+    pub inputs: BTreeMap<String, File>,
+    #[serde(default)]
+    pub artifacts: Vec<Artifact>,
+}
+
+/// the fingerprint of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct File {
+    pub hash: String,
+    pub size: u64,
+    /// the modification time in nanoseconds since the unix epoch
+    pub modified: u64,
+}
+
+impl Fingerprint {
+    /// the fingerprint file of the project
+    pub fn path(context: &Context) -> PathBuf {
+        path(&context.profile_dir(), &context.project.name)
+    }
+    /// load the fingerprint file, `None` if it does not exist or is invalid
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read(path).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| Error::IOError(parent.to_path_buf(), e))?;
+        }
+        let content = serde_json::to_vec_pretty(self)
+            .map_err(|e| Error::SerializeError(path.to_path_buf(), e))?;
+        std::fs::write(path, content).map_err(|e| Error::IOError(path.to_path_buf(), e))
+    }
+    /// the fingerprint of the project (without artifacts), the hashes of the files which were not
+    /// changed since `previous` are reused
+    pub fn compute(
+        context: &Context,
+        binary: Option<&str>,
+        toolchain: Vec<String>,
+        previous: Option<&Self>,
+    ) -> Result<Self> {
+        let mut dependencies = BTreeMap::new();
+        for dependency in context.dependencies {
+            dependencies.insert(
+                dependency.name.clone(),
+                dependency_hash(&context.profile_dir(), dependency)?,
+            );
+        }
+        let env = ENV_NAMES
+            .iter()
+            .filter_map(|&name| {
+                let value = std::env::var_os(name)?;
+                Some((name.to_owned(), value.to_string_lossy().into_owned()))
+            })
+            .collect();
+        let mut files = BTreeMap::new();
+        visit(
+            &context.project.dir,
+            "",
+            previous.map(|previous| &previous.files),
+            &mut files,
+        )?;
+        // the inputs of the last build, a removed input has an empty hash
+        let mut inputs = BTreeMap::new();
+        for (input, previous) in previous.into_iter().flat_map(|previous| &previous.inputs) {
+            let file = match file(Path::new(input), Some(previous)) {
+                Err(Error::IOError(_, e)) if e.kind() == io::ErrorKind::NotFound => File {
+                    hash: String::new(),
+                    size: 0,
+                    modified: 0,
+                },
+                file => file?,
+            };
+            inputs.insert(input.clone(), file);
+        }
+        Ok(Self {
+            toolchain,
+            binary: binary.map(str::to_owned),
+            config: context.project.config.clone(),
+            profile: context.settings.clone(),
+            env,
+            dependencies,
+            files,
+            inputs,
+            artifacts: Vec::new(),
+        })
+    }
+    /// add the files which were generated by the build in the project directory, and replace the
+    /// inputs by the `inputs` of the build, the files and inputs which were changed during the
+    /// build keep their hashes from before the build
+    pub fn update(&mut self, context: &Context, inputs: &[PathBuf]) -> Result<()> {
+        let mut files = BTreeMap::new();
+        visit(&context.project.dir, "", Some(&self.files), &mut files)?;
+        for (path, file) in files {
+            self.files.entry(path).or_insert(file);
+        }
+        let mut previous = std::mem::take(&mut self.inputs);
+        for input in inputs {
+            let key = input.to_string_lossy().into_owned();
+            let file = match previous.remove(&key) {
+                Some(file) => file,
+                None if input.exists() => file(input, None)?,
+                None => continue,
+            };
+            self.inputs.insert(key, file);
+        }
+        Ok(())
+    }
+    /// the inputs which were changed since `previous`, and the artifacts which were removed
+    pub fn changes(&self, previous: Option<&Self>) -> Vec<String> {
+        let Some(previous) = previous else {
+            return vec!["the project was not built yet".to_owned()];
+        };
+        let mut changes = Vec::new();
+        let length = self.toolchain.len().max(previous.toolchain.len());
+        for index in 0..length {
+            let (old, new) = (previous.toolchain.get(index), self.toolchain.get(index));
+            if old != new {
+                changes.push(format!(
+                    "toolchain version '{}' -> '{}'",
+                    old.map_or("", String::as_str),
+                    new.map_or("", String::as_str)
+                ));
+            }
+        }
+        if self.binary != previous.binary {
+            let binary = |binary: &Option<String>| match binary {
+                Some(binary) => format!("'{}'", binary),
+                None => "all".to_owned(),
+            };
+            changes.push(format!(
+                "binary {} -> {}",
+                binary(&previous.binary),
+                binary(&self.binary)
+            ));
+        }
+        diff(
+            "config key",
+            &flatten(&previous.config),
+            &flatten(&self.config),
+            &mut changes,
+        );
+        diff(
+            "profile field",
+            &flatten(&previous.profile),
+            &flatten(&self.profile),
+            &mut changes,
+        );
+        diff(
+            "environment variable",
+            &previous.env,
+            &self.env,
+            &mut changes,
+        );
+        diff(
+            "dependency",
+            &previous.dependencies,
+            &self.dependencies,
+            &mut changes,
+        );
+        let hashes = |files: &BTreeMap<String, File>| {
+            files
+                .iter()
+                .map(|(path, file)| (path.clone(), file.hash.clone()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        diff(
+            "file",
+            &hashes(&previous.files),
+            &hashes(&self.files),
+            &mut changes,
+        );
+        diff(
+            "input",
+            &hashes(&previous.inputs),
+            &hashes(&self.inputs),
+            &mut changes,
+        );
+        for artifact in &previous.artifacts {
+            if !artifact.path.exists() {
+                changes.push(format!(
+                    "artifact '{}' was removed",
+                    artifact.path.display()
+                ));
+            }
+        }
+        changes
+    }
+}
+
+/// describe the keys which were added, removed or changed from `old` to `new`
+fn diff(
+    kind: &str,
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    changes: &mut Vec<String>,
+) {
+    let keys = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    for key in keys {
+        match (old.get(key), new.get(key)) {
+            (Some(old), Some(new)) if old != new => {
+                changes.push(format!("{} '{}' changed", kind, key));
+            }
+            (Some(_), None) => changes.push(format!("{} '{}' was removed", kind, key)),
+            (None, Some(_)) => changes.push(format!("{} '{}' was added", kind, key)),
+            _ => {}
+        }
+    }
+}
+
+/// the leaves of `value` by their dotted keys (e.g. `config.version`)
+fn flatten(value: &impl Serialize) -> BTreeMap<String, String> {
+    fn visit(prefix: &str, value: &serde_json::Value, leaves: &mut BTreeMap<String, String>) {
+        match value {
+            serde_json::Value::Object(object) => {
+                for (key, value) in object {
+                    let key = match prefix {
+                        "" => key.clone(),
+                        _ => format!("{}.{}", prefix, key),
+                    };
+                    visit(&key, value, leaves);
+                }
+            }
+            value => {
+                leaves.insert(prefix.to_owned(), value.to_string());
+            }
+        }
+    }
+    let mut leaves = BTreeMap::new();
+    if let Ok(value) = serde_json::to_value(value) {
+        visit("", &value, &mut leaves);
+    }
+    leaves
+}
+
+/// fingerprint the files in `dir` (recursively), `prefix` is the path of `dir` relative to the
+/// project directory
+fn visit(
+    dir: &Path,
+    prefix: &str,
+    previous: Option<&BTreeMap<String, File>>,
+    files: &mut BTreeMap<String, File>,
+) -> Result<()> {
+    for path in crate::fs::sorted_entries(dir).map_err(|e| Error::IOError(dir.to_path_buf(), e))? {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let relative = format!("{}{}", prefix, name);
+        let metadata =
+            std::fs::symlink_metadata(&path).map_err(|e| Error::IOError(path.clone(), e))?;
+        if metadata.is_dir() {
+            if !name.starts_with('.') && name != crate::build::TARGET_DIR_NAME {
+                visit(&path, &format!("{}/", relative), previous, files)?;
+            }
+            continue;
+        }
+        let file = file(&path, previous.and_then(|previous| previous.get(&relative)))?;
+        files.insert(relative, file);
+    }
+    Ok(())
+}
+
+/// fingerprint the file `path`, its hash is reused from `previous` if its size and modification
+/// time were not changed
+fn file(path: &Path, previous: Option<&File>) -> Result<File> {
+    let metadata =
+        std::fs::symlink_metadata(path).map_err(|e| Error::IOError(path.to_path_buf(), e))?;
+    let size = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_nanos() as u64);
+    let hash = match previous {
+        Some(file) if file.size == size && file.modified == modified => file.hash.clone(),
+        _ => crate::fs::hash_tree(path).map_err(|e| Error::IOError(path.to_path_buf(), e))?,
+    };
+    Ok(File {
+        hash,
+        size,
+        modified,
+    })
+}
+
+/// the fingerprint file of the project `name` in the profile directory
+fn path(profile_dir: &Path, name: &str) -> PathBuf {
+    profile_dir
+        .join(FINGERPRINT_DIR_NAME)
+        .join(format!("{}.json", name))
+}
+
+/// the hash of the artifacts of the dependency, and of its files (e.g. the headers which are
+/// included, but do not change the artifacts)
+fn dependency_hash(profile_dir: &Path, dependency: &Dependency) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for artifact in &dependency.artifacts {
+        let hash = crate::fs::hash_tree(&artifact.path)
+            .map_err(|e| Error::IOError(artifact.path.clone(), e))?;
+        hasher.update(artifact.path.as_os_str().as_encoded_bytes());
+        hasher.update(hash);
+    }
+    if let Some(fingerprint) = Fingerprint::load(&path(profile_dir, &dependency.name)) {
+        for (path, file) in &fingerprint.files {
+            hasher.update(path.as_bytes());
+            hasher.update(file.hash.as_bytes());
+        }
+    }
+    Ok(crate::fs::hex(&hasher.finalize()))
+}
@@ -0,0 +1,173 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! file system helpers
+//!
+
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// the number of files and bytes in a file or directory
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl DiskUsage {
+    /// the disk usage of `path` (symbolic links are not followed)
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        if !metadata.is_dir() {
+            return Ok(Self {
+                files: 1,
+                bytes: metadata.len(),
+            });
+        }
+        let mut usage = Self::default();
+        for entry in std::fs::read_dir(path)? {
+            usage += Self::of(&entry?.path())?;
+        }
+        Ok(usage)
+    }
+}
+
+impl std::ops::AddAssign for DiskUsage {
+    fn add_assign(&mut self, rhs: Self) {
+        self.files += rhs.files;
+        self.bytes += rhs.bytes;
+    }
+}
+
+/// format `bytes` for humans (e.g. `1.5 MiB`)
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// move `from` to `to`, falling back to copy & remove across file systems
+///
+/// the parent directory of `to` will be created if needed, `to` must not exist.
+pub fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::symlink_metadata(to).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' already exists", to.display()),
+        ));
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_path(from, to)?;
+            if std::fs::symlink_metadata(from)?.is_dir() {
+                std::fs::remove_dir_all(from)
+            } else {
+                std::fs::remove_file(from)
+            }
+        }
+        res => res,
+    }
+}
+
+/// copy the file or directory `from` to `to` recursively
+pub fn copy_path(from: &Path, to: &Path) -> io::Result<()> {
+    if std::fs::symlink_metadata(from)?.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// the entries of the directory `dir`, sorted by name
+pub fn sorted_entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// the sha-256 hash of a file or directory tree (names, types and contents) in hex
+pub fn hash_tree(path: &Path) -> io::Result<String> {
+    hash(path, true)
+}
+
+/// the sha-256 hash of a file or directory tree in hex, like [`hash_tree`], but the files are
+/// hashed by their sizes and modification times instead of their contents, which is cheap for
+/// large trees (e.g. `target`)
+pub fn hash_tree_metadata(path: &Path) -> io::Result<String> {
+    hash(path, false)
+}
+
+fn hash(path: &Path, contents: bool) -> io::Result<String> {
+    fn update(hasher: &mut Sha256, path: &Path, name: &[u8], contents: bool) -> io::Result<()> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        hasher.update((name.len() as u64).to_le_bytes());
+        hasher.update(name);
+        if metadata.is_dir() {
+            hasher.update(b"d");
+            for entry in sorted_entries(path)? {
+                let name = entry
+                    .file_name()
+                    .unwrap_or_default()
+                    .as_encoded_bytes()
+                    .to_vec();
+                update(hasher, &entry, &name, contents)?;
+            }
+            hasher.update(b"e");
+        } else if metadata.is_symlink() {
+            hasher.update(b"l");
+            hasher.update(std::fs::read_link(path)?.as_os_str().as_encoded_bytes());
+        } else if contents {
+            let content = std::fs::read(path)?;
+            hasher.update(b"f");
+            hasher.update((content.len() as u64).to_le_bytes());
+            hasher.update(&content);
+        } else {
+            let modified = metadata
+                .modified()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            hasher.update(b"m");
+            hasher.update(metadata.len().to_le_bytes());
+            hasher.update(modified.as_nanos().to_le_bytes());
+        }
+        Ok(())
+    }
+    let mut hasher = Sha256::new();
+    update(&mut hasher, path, b"", contents)?;
+    Ok(hex(&hasher.finalize()))
+}
+
+/// `bytes` in lowercase hex
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
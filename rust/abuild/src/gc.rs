@@ -0,0 +1,323 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! garbage collection of the `target` directory
+//!
+//! > the outputs of a project in a profile directory (`target/[<triple>/]<profile>`) are its
+//! > build directory (`build/<project>`), the cache of its dependencies (`deps/<project>`), its
+//! > fingerprint and its binaries.
+//! > the last use of the outputs is recorded in `target/access.json` whenever the project is
+//! > built or run.
+//! >
+//! > `abuild gc` removes:
+//! >
+//! > 1. the outputs which were not used for `--older-than`
+//! > 2. the least recently used outputs, until the `target` directory is not larger than
+//! >    `--max-size`
+//! > 3. the profile directories which have no outputs left
+//!
+//! + ![note] outputs without a record (e.g. built by an older abuild) were last used when their
+//!   fingerprint was written.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::build::{ArtifactKind, BUILD_DIR_NAME, DEPS_DIR_NAME, TARGET_DIR_NAME};
+use crate::fingerprint::{FINGERPRINT_DIR_NAME, Fingerprint};
+use crate::fs::DiskUsage;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const ACCESS_FILE_NAME: &str = "access.json";
+
+/// the last uses of the outputs (`<profile dir relative to target>/<project>` → seconds since
+/// the unix epoch)
+pub type Records = BTreeMap<String, u64>;
+
+/// the outputs of a project in a profile directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    pub key: String,
+    pub profile_dir: PathBuf,
+    pub project: String,
+    pub paths: Vec<PathBuf>,
+    pub usage: DiskUsage,
+    /// seconds since the unix epoch
+    pub last_used: u64,
+}
+
+/// the access file of the workspace (`target/access.json`)
+pub fn access_file(workspace_dir: &Path) -> PathBuf {
+    workspace_dir.join(TARGET_DIR_NAME).join(ACCESS_FILE_NAME)
+}
+
+/// load the records, no records if the file does not exist or is invalid
+pub fn load(workspace_dir: &Path) -> Records {
+    std::fs::read(access_file(workspace_dir))
+        .ok()
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(workspace_dir: &Path, records: &Records) -> io::Result<()> {
+    let path = access_file(workspace_dir);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_vec_pretty(records)?)
+}
+
+/// record that the projects were used in the profile directory now
+pub fn record(workspace_dir: &Path, profile_dir: &Path, projects: &[&str]) -> io::Result<()> {
+    let mut records = load(workspace_dir);
+    let now = now();
+    for project in projects {
+        records.insert(key(workspace_dir, profile_dir, project), now);
+    }
+    save(workspace_dir, &records)
+}
+
+/// the outputs of the project in the profile directory
+pub fn outputs(profile_dir: &Path, project: &str) -> Vec<PathBuf> {
+    let fingerprint_file = profile_dir
+        .join(FINGERPRINT_DIR_NAME)
+        .join(format!("{}.json", project));
+    let mut paths = Vec::new();
+    if let Some(fingerprint) = Fingerprint::load(&fingerprint_file) {
+        let profile_dir = canonical(profile_dir);
+        paths.extend(
+            fingerprint
+                .artifacts
+                .into_iter()
+                .filter(|artifact| artifact.kind == ArtifactKind::Binary)
+                .map(|artifact| artifact.path)
+                .filter(|path| path.parent().map(canonical).as_ref() == Some(&profile_dir)),
+        );
+    }
+    paths.push(profile_dir.join(BUILD_DIR_NAME).join(project));
+    paths.push(profile_dir.join(DEPS_DIR_NAME).join(project));
+    paths.push(fingerprint_file);
+    paths
+}
+
+/// the profile directories in `dir` (`target` or `target/<triple>`)
+pub fn profile_dirs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    match crate::fs::sorted_entries(dir) {
+        Ok(entries) => Ok(entries
+            .into_iter()
+            .filter(|dir| is_profile_dir(dir))
+            .collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// the profile directories of all targets in `target_dir` (`target/<profile>` and
+/// `target/<triple>/<profile>`)
+pub fn all_profile_dirs(target_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut dirs = profile_dirs(target_dir)?;
+    for entry in crate::fs::sorted_entries(target_dir).unwrap_or_default() {
+        if entry.is_dir() && !is_profile_dir(&entry) {
+            dirs.extend(profile_dirs(&entry)?);
+        }
+    }
+    Ok(dirs)
+}
+
+/// the outputs of all projects in all profile directories of the workspace, the least recently
+/// used first
+pub fn units(workspace_dir: &Path, records: &Records) -> io::Result<Vec<Unit>> {
+    let mut units = Vec::new();
+    for profile_dir in all_profile_dirs(&workspace_dir.join(TARGET_DIR_NAME))? {
+        for project in projects(&profile_dir)? {
+            let key = key(workspace_dir, &profile_dir, &project);
+            let paths = outputs(&profile_dir, &project)
+                .into_iter()
+                .filter(|path| std::fs::symlink_metadata(path).is_ok())
+                .collect::<Vec<_>>();
+            let mut usage = DiskUsage::default();
+            for path in &paths {
+                usage += DiskUsage::of(path)?;
+            }
+            let last_used = match records.get(&key) {
+                Some(last_used) => *last_used,
+                None => modified(
+                    &profile_dir
+                        .join(FINGERPRINT_DIR_NAME)
+                        .join(format!("{}.json", project)),
+                ),
+            };
+            units.push(Unit {
+                key,
+                profile_dir: profile_dir.clone(),
+                project,
+                paths,
+                usage,
+                last_used,
+            });
+        }
+    }
+    units.sort_by_key(|unit| unit.last_used);
+    Ok(units)
+}
+
+/// whether `dir` has any outputs
+pub fn has_outputs(profile_dir: &Path) -> bool {
+    projects(profile_dir).is_ok_and(|projects| !projects.is_empty())
+}
+
+/// seconds since the unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs())
+}
+
+/// the key of the record of the project in the profile directory
+fn key(workspace_dir: &Path, profile_dir: &Path, project: &str) -> String {
+    let target_dir = workspace_dir.join(TARGET_DIR_NAME);
+    let relative = profile_dir.strip_prefix(&target_dir).unwrap_or(profile_dir);
+    let mut components = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    components.push(project.to_owned());
+    components.join("/")
+}
+
+fn is_profile_dir(dir: &Path) -> bool {
+    [BUILD_DIR_NAME, DEPS_DIR_NAME, FINGERPRINT_DIR_NAME]
+        .into_iter()
+        .any(|name| dir.join(name).is_dir())
+}
+
+/// the canonical form of `path`, `path` itself if it cannot be resolved
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+/// the projects which have outputs in the profile directory
+fn projects(profile_dir: &Path) -> io::Result<Vec<String>> {
+    let mut projects = Vec::new();
+    for (dir, extension) in [
+        (BUILD_DIR_NAME, None),
+        (DEPS_DIR_NAME, None),
+        (FINGERPRINT_DIR_NAME, Some("json")),
+    ] {
+        let entries = match crate::fs::sorted_entries(&profile_dir.join(dir)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let name = match extension {
+                Some(extension) if entry.extension().is_some_and(|e| e == extension) => {
+                    entry.file_stem()
+                }
+                Some(_) => None,
+                None => entry.file_name(),
+            };
+            if let Some(name) = name.map(|name| name.to_string_lossy().into_owned())
+                && !projects.contains(&name)
+            {
+                projects.push(name);
+            }
+        }
+    }
+    Ok(projects)
+}
+
+/// the modification time of `path` in seconds since the unix epoch (0 if unknown)
+fn modified(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units_by_last_use() {
+        let workspace_dir = std::env::temp_dir().join(format!("abuild-gc-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&workspace_dir);
+        let target_dir = workspace_dir.join(TARGET_DIR_NAME);
+        let debug_dir = target_dir.join("debug");
+        let release_dir = target_dir.join("aarch64-unknown-linux-gnu").join("release");
+        for (profile_dir, project) in [
+            (&debug_dir, "app"),
+            (&debug_dir, "lib"),
+            (&release_dir, "app"),
+        ] {
+            let build_dir = profile_dir.join(BUILD_DIR_NAME).join(project);
+            std::fs::create_dir_all(&build_dir).unwrap();
+            std::fs::write(build_dir.join("main.o"), "object").unwrap();
+        }
+        std::fs::create_dir_all(debug_dir.join(FINGERPRINT_DIR_NAME)).unwrap();
+        std::fs::write(debug_dir.join(FINGERPRINT_DIR_NAME).join("app.json"), "{}").unwrap();
+        std::fs::create_dir_all(debug_dir.join(DEPS_DIR_NAME).join("lib")).unwrap();
+        // only a dependency cache
+        let deps_dir = target_dir.join("x86_64-pc-windows-msvc").join("debug");
+        std::fs::create_dir_all(deps_dir.join(DEPS_DIR_NAME).join("app")).unwrap();
+        // not a profile directory
+        std::fs::create_dir_all(target_dir.join("other")).unwrap();
+
+        assert_eq!(profile_dirs(&target_dir).unwrap(), [debug_dir.as_path()]);
+        assert_eq!(
+            all_profile_dirs(&target_dir).unwrap(),
+            [debug_dir.as_path(), &release_dir, &deps_dir]
+        );
+        record(&workspace_dir, &debug_dir, &["lib"]).unwrap();
+        let mut records = load(&workspace_dir);
+        assert!(records.contains_key("debug/lib"));
+        records.insert("aarch64-unknown-linux-gnu/release/app".to_owned(), 1);
+        let units = units(&workspace_dir, &records).unwrap();
+        let keys = units
+            .iter()
+            .map(|unit| unit.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "x86_64-pc-windows-msvc/debug/app",
+                "aarch64-unknown-linux-gnu/release/app",
+                "debug/app",
+                "debug/lib"
+            ]
+        );
+        assert_eq!(units[2].usage, DiskUsage { files: 2, bytes: 8 });
+        assert_eq!(
+            units[2].paths,
+            [
+                debug_dir.join(BUILD_DIR_NAME).join("app"),
+                debug_dir.join(FINGERPRINT_DIR_NAME).join("app.json")
+            ]
+        );
+        assert_eq!(
+            units[3].paths,
+            [
+                debug_dir.join(BUILD_DIR_NAME).join("lib"),
+                debug_dir.join(DEPS_DIR_NAME).join("lib")
+            ]
+        );
+        assert!(has_outputs(&release_dir));
+        assert!(!has_outputs(&target_dir.join("other")));
+        std::fs::remove_dir_all(&workspace_dir).unwrap();
+    }
+}
@@ -0,0 +1,513 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! operation journal of a workspace (`.abuild/journal.toml`), which backs `abuild undo` and
+//! `abuild redo`.
+//!
+//! > every mutating command records an operation, which is a list of changes:
+//! >
+//! > - `file`: a file was written or deleted, the content before and after is recorded.
+//! > - `move`: a file or directory was moved (e.g. into the trash).
+//! > - `create`: a directory tree was created, undoing it moves the tree into the trash.
+//!
+//! + ![note] paths are relative to the workspace directory.
+//! + ![note] an operation is not undone (or redone) if any of its files were changed since.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const JOURNAL_FILE_NAME: &str = "journal.toml";
+
+#[derive(Debug)]
+pub enum Error {
+    IOError(PathBuf, io::Error),
+    ConfigError(crate::config::Error),
+    ModifiedError(PathBuf),
+}
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::IOError(path, e) => write!(f, "IO error: {}: {}", path.display(), e),
+            Error::ConfigError(e) => write!(f, "Config error: {}", e),
+            Error::ModifiedError(path) => {
+                write!(f, "'{}' was modified since the operation", path.display())
+            }
+        }
+    }
+}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::IOError(_, e) => Some(e),
+            Error::ConfigError(e) => Some(e),
+            Error::ModifiedError(_) => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// the undo and redo stacks of a workspace
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Journal {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub undo: Vec<Operation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redo: Vec<Operation>,
+}
+
+/// a reversible operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Operation {
+    /// the description of the operation (e.g. `create project 'a'`)
+    pub name: String,
+    /// the time of the operation (seconds since the unix epoch)
+    pub time: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum Change {
+    /// a file was written (`None`: the file does not exist)
+    File {
+        path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        before: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        after: Option<String>,
+    },
+    /// a file or directory was moved
+    Move {
+        from: PathBuf,
+        to: PathBuf,
+        hash: String,
+    },
+    /// a directory tree was created, `trash` is where it was moved by undo
+    Create {
+        path: PathBuf,
+        hash: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        trash: Option<PathBuf>,
+    },
+}
+
+/// records the changes of an operation
+#[derive(Debug)]
+pub struct Recorder {
+    workspace_dir: PathBuf,
+    operation: Operation,
+}
+
+impl Journal {
+    /// the path of the journal of the workspace
+    pub fn path(workspace_dir: &Path) -> PathBuf {
+        crate::app_config_dir_in(workspace_dir).join(JOURNAL_FILE_NAME)
+    }
+    /// load the journal of the workspace, an empty journal if it does not exist
+    pub fn load(workspace_dir: &Path) -> Result<Self> {
+        let path = Self::path(workspace_dir);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        crate::config::read(&path).map_err(Error::ConfigError)
+    }
+    /// save the journal of the workspace
+    pub fn save(&self, workspace_dir: &Path) -> Result<()> {
+        crate::config::write(&Self::path(workspace_dir), self).map_err(Error::ConfigError)
+    }
+    /// the index of the last operation in `stack` in the scope of `project` and `profile`
+    ///
+    /// an operation is in the scope if it has the same project (and profile) when given.
+    pub fn last_in_scope(
+        stack: &[Operation],
+        project: Option<&str>,
+        profile: Option<&str>,
+    ) -> Option<usize> {
+        stack.iter().rposition(|operation| {
+            (project.is_none() || operation.project.as_deref() == project)
+                && (profile.is_none() || operation.profile.as_deref() == profile)
+        })
+    }
+}
+
+impl Operation {
+    /// check that the files are in the state after the operation, then undo it
+    pub fn undo(&mut self, workspace_dir: &Path) -> Result<Vec<String>> {
+        for change in &self.changes {
+            change.check(workspace_dir, true)?;
+        }
+        let mut messages = Vec::new();
+        for change in self.changes.iter_mut().rev() {
+            messages.push(change.undo(workspace_dir)?);
+        }
+        Ok(messages)
+    }
+    /// check that the files are in the state before the operation, then redo it
+    pub fn redo(&mut self, workspace_dir: &Path) -> Result<Vec<String>> {
+        for change in &self.changes {
+            change.check(workspace_dir, false)?;
+        }
+        let mut messages = Vec::new();
+        for change in self.changes.iter_mut() {
+            messages.push(change.redo(workspace_dir)?);
+        }
+        Ok(messages)
+    }
+}
+
+impl Change {
+    /// check that the files are in the state after (or before) the change
+    fn check(&self, workspace_dir: &Path, after_change: bool) -> Result<()> {
+        match self {
+            Change::File {
+                path,
+                before,
+                after,
+            } => {
+                let expected = if after_change { after } else { before };
+                if &read_file(&workspace_dir.join(path))? != expected {
+                    return Err(Error::ModifiedError(path.clone()));
+                }
+            }
+            Change::Move { from, to, hash } => {
+                let (path, absent) = if after_change { (to, from) } else { (from, to) };
+                let full_path = workspace_dir.join(path);
+                if std::fs::symlink_metadata(&full_path).is_err()
+                    || hash_moved(from, &full_path)? != *hash
+                {
+                    return Err(Error::ModifiedError(path.clone()));
+                }
+                check_absent(workspace_dir, absent)?;
+            }
+            Change::Create { path, hash, trash } => match (after_change, trash) {
+                (true, _) => check_tree(workspace_dir, path, hash)?,
+                (false, Some(trash)) => {
+                    check_tree(workspace_dir, trash, hash)?;
+                    check_absent(workspace_dir, path)?;
+                }
+                (false, None) => return Err(Error::ModifiedError(path.clone())),
+            },
+        }
+        Ok(())
+    }
+    fn undo(&mut self, workspace_dir: &Path) -> Result<String> {
+        match self {
+            Change::File { path, before, .. } => {
+                write_file(&workspace_dir.join(&*path), before.as_deref())?;
+                Ok(format!("restored '{}'.", path.display()))
+            }
+            Change::Move { from, to, .. } => {
+                move_path(workspace_dir, to, from)?;
+                Ok(format!(
+                    "moved '{}' back to '{}'.",
+                    to.display(),
+                    from.display()
+                ))
+            }
+            Change::Create { path, trash, .. } => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let entry = crate::trash::new_entry(workspace_dir, "undo", &name)
+                    .map_err(|e| Error::IOError(workspace_dir.to_path_buf(), e))?;
+                let entry = entry
+                    .strip_prefix(workspace_dir)
+                    .unwrap_or(&entry)
+                    .join(&name);
+                move_path(workspace_dir, path, &entry)?;
+                let message = format!("moved '{}' to '{}'.", path.display(), entry.display());
+                *trash = Some(entry);
+                Ok(message)
+            }
+        }
+    }
+    fn redo(&mut self, workspace_dir: &Path) -> Result<String> {
+        match self {
+            Change::File { path, after, .. } => {
+                write_file(&workspace_dir.join(&*path), after.as_deref())?;
+                Ok(format!("rewrote '{}'.", path.display()))
+            }
+            Change::Move { from, to, .. } => {
+                move_path(workspace_dir, from, to)?;
+                Ok(format!("moved '{}' to '{}'.", from.display(), to.display()))
+            }
+            Change::Create { path, trash, .. } => {
+                let Some(from) = trash.take() else {
+                    return Err(Error::ModifiedError(path.clone()));
+                };
+                move_path(workspace_dir, &from, path)?;
+                Ok(format!(
+                    "moved '{}' back to '{}'.",
+                    from.display(),
+                    path.display()
+                ))
+            }
+        }
+    }
+}
+
+impl Recorder {
+    pub fn new(
+        workspace_dir: &Path,
+        name: impl Into<String>,
+        project: Option<&str>,
+        profile: Option<&str>,
+    ) -> Self {
+        Self {
+            workspace_dir: workspace_dir.to_path_buf(),
+            operation: Operation {
+                name: name.into(),
+                time: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs()),
+                project: project.map(str::to_owned),
+                profile: profile.map(str::to_owned),
+                changes: Vec::new(),
+            },
+        }
+    }
+    /// record the content of the file before it is written (or deleted)
+    pub fn write(&mut self, path: &Path) -> Result<()> {
+        let before = read_file(path)?;
+        self.operation.changes.push(Change::File {
+            path: self.relative(path),
+            before,
+            after: None,
+        });
+        Ok(())
+    }
+    /// record a file or directory that was moved
+    pub fn moved(&mut self, from: &Path, to: &Path) -> Result<()> {
+        let from = self.relative(from);
+        let hash = hash_moved(&from, to)?;
+        self.operation.changes.push(Change::Move {
+            from,
+            to: self.relative(to),
+            hash,
+        });
+        Ok(())
+    }
+    /// record a directory tree (or file) that was created
+    pub fn created(&mut self, path: &Path) -> Result<()> {
+        let hash = hash_tree(path)?;
+        self.operation.changes.push(Change::Create {
+            path: self.relative(path),
+            hash,
+            trash: None,
+        });
+        Ok(())
+    }
+    /// record the content of the written files after the operation, and push the operation
+    /// into the journal of the workspace
+    pub fn finish(mut self) -> Result<()> {
+        for change in &mut self.operation.changes {
+            if let Change::File { path, after, .. } = change {
+                *after = read_file(&self.workspace_dir.join(&*path))?;
+            }
+        }
+        let mut journal = Journal::load(&self.workspace_dir)?;
+        journal.undo.push(self.operation);
+        journal.redo.clear();
+        journal.save(&self.workspace_dir)
+    }
+    fn relative(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.workspace_dir)
+            .unwrap_or(path)
+            .to_path_buf()
+    }
+}
+
+/// the hash of a file or directory tree
+pub fn hash_tree(path: &Path) -> Result<String> {
+    crate::fs::hash_tree(path).map_err(|e| Error::IOError(path.to_path_buf(), e))
+}
+
+/// the hash of a tree which was moved from `from` (relative to the workspace), the target
+/// directory is only checked by the sizes and modification times of its files, as hashing the
+/// contents of the build outputs is slow
+fn hash_moved(from: &Path, path: &Path) -> Result<String> {
+    if from == Path::new(crate::build::TARGET_DIR_NAME) {
+        crate::fs::hash_tree_metadata(path).map_err(|e| Error::IOError(path.to_path_buf(), e))
+    } else {
+        hash_tree(path)
+    }
+}
+
+fn check_tree(workspace_dir: &Path, path: &Path, hash: &str) -> Result<()> {
+    let full_path = workspace_dir.join(path);
+    if std::fs::symlink_metadata(&full_path).is_err() || hash_tree(&full_path)? != hash {
+        return Err(Error::ModifiedError(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+fn check_absent(workspace_dir: &Path, path: &Path) -> Result<()> {
+    if std::fs::symlink_metadata(workspace_dir.join(path)).is_ok() {
+        return Err(Error::ModifiedError(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+fn move_path(workspace_dir: &Path, from: &Path, to: &Path) -> Result<()> {
+    crate::fs::move_path(&workspace_dir.join(from), &workspace_dir.join(to))
+        .map_err(|e| Error::IOError(from.to_path_buf(), e))
+}
+
+fn read_file(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::IOError(path.to_path_buf(), e)),
+    }
+}
+
+fn write_file(path: &Path, content: Option<&str>) -> Result<()> {
+    let io_error = |e| Error::IOError(path.to_path_buf(), e);
+    match content {
+        Some(content) => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(io_error)?;
+            }
+            std::fs::write(path, content).map_err(io_error)
+        }
+        None => match std::fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(io_error(e)),
+            _ => Ok(()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// an empty workspace directory for the test `name`
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("abuild-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// the last recorded operation of the workspace
+    fn last(workspace_dir: &Path) -> Operation {
+        Journal::load(workspace_dir).unwrap().undo.pop().unwrap()
+    }
+
+    #[test]
+    fn undo_and_redo_changes() {
+        let workspace_dir = temp_dir("journal");
+        let file = workspace_dir.join("config.toml");
+        std::fs::write(&file, "before").unwrap();
+        let from = workspace_dir.join("app");
+        std::fs::create_dir(&from).unwrap();
+        std::fs::write(from.join("main.c"), "int main;").unwrap();
+        let to = workspace_dir.join("moved");
+        let created = workspace_dir.join("lib");
+
+        let mut recorder = Recorder::new(&workspace_dir, "test", None, None);
+        recorder.write(&file).unwrap();
+        std::fs::write(&file, "after").unwrap();
+        std::fs::rename(&from, &to).unwrap();
+        recorder.moved(&from, &to).unwrap();
+        std::fs::create_dir(&created).unwrap();
+        recorder.created(&created).unwrap();
+        recorder.finish().unwrap();
+
+        let mut operation = last(&workspace_dir);
+        assert_eq!(operation.changes.len(), 3);
+        operation.undo(&workspace_dir).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "before");
+        assert!(from.join("main.c").is_file() && !to.exists());
+        assert!(!created.exists());
+        assert!(matches!(
+            &operation.changes[2],
+            Change::Create { trash: Some(trash), .. } if workspace_dir.join(trash).is_dir()
+        ));
+
+        operation.redo(&workspace_dir).unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "after");
+        assert!(to.join("main.c").is_file() && !from.exists());
+        assert!(created.is_dir());
+        std::fs::remove_dir_all(&workspace_dir).unwrap();
+    }
+
+    #[test]
+    fn modified_files_are_not_undone() {
+        let workspace_dir = temp_dir("journal-modified");
+        let file = workspace_dir.join("config.toml");
+        let mut recorder = Recorder::new(&workspace_dir, "test", Some("app"), None);
+        recorder.write(&file).unwrap();
+        std::fs::write(&file, "after").unwrap();
+        recorder.finish().unwrap();
+
+        let journal = Journal::load(&workspace_dir).unwrap();
+        assert_eq!(
+            Journal::last_in_scope(&journal.undo, Some("app"), None),
+            Some(0)
+        );
+        assert_eq!(
+            Journal::last_in_scope(&journal.undo, Some("lib"), None),
+            None
+        );
+        std::fs::write(&file, "changed").unwrap();
+        let mut operation = last(&workspace_dir);
+        assert!(matches!(
+            operation.undo(&workspace_dir),
+            Err(Error::ModifiedError(path)) if path == Path::new("config.toml")
+        ));
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "changed");
+        std::fs::remove_dir_all(&workspace_dir).unwrap();
+    }
+
+    #[test]
+    fn moved_target_is_checked_by_metadata() {
+        let workspace_dir = temp_dir("journal-target");
+        let from = workspace_dir.join(crate::build::TARGET_DIR_NAME);
+        std::fs::create_dir(&from).unwrap();
+        std::fs::write(from.join("app"), "binary").unwrap();
+        let to = workspace_dir.join("trash");
+        let mut recorder = Recorder::new(&workspace_dir, "test", None, None);
+        std::fs::rename(&from, &to).unwrap();
+        recorder.moved(&from, &to).unwrap();
+        recorder.finish().unwrap();
+
+        let mut operation = last(&workspace_dir);
+        assert!(matches!(
+            &operation.changes[0],
+            Change::Move { hash, .. } if *hash == crate::fs::hash_tree_metadata(&to).unwrap()
+        ));
+        std::fs::write(to.join("app"), "rebuilt binary").unwrap();
+        assert!(matches!(
+            operation.undo(&workspace_dir),
+            Err(Error::ModifiedError(path)) if path == Path::new("trash")
+        ));
+        std::fs::remove_dir_all(&workspace_dir).unwrap();
+    }
+}
@@ -37,9 +37,86 @@
 //! > >
 //! > > > ![feature] msbuild(*.csproj)
 //!
+//! + ![note] msbuild is run by the .NET SDK (`dotnet`), msbuild(*.sln) of c/c++ is not supported
+//!   yet.
+//!
 //! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
 //!
 //! [bug]: https://img.shields.io/badge/bug-red.svg
 //!
 //! [feature]: https://img.shields.io/badge/feature-orange.svg
 //!
+
+pub mod cargo;
+
+pub mod cmake;
+
+pub mod make;
+
+pub mod msbuild;
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// the language of a project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Rust,
+    C,
+    Cpp,
+    #[value(name = "csharp")]
+    CSharp,
+}
+
+/// the tool used to build a project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildTool {
+    Cargo,
+    Make,
+    #[value(name = "cmake")]
+    CMake,
+    #[value(name = "msbuild")]
+    MSBuild,
+}
+
+impl Language {
+    /// the build tools supported by this language, the first one is the default
+    pub fn build_tools(&self) -> &'static [BuildTool] {
+        match self {
+            Language::Rust => &[BuildTool::Cargo],
+            // msbuild of c/c++ has no backend yet
+            Language::C | Language::Cpp => &[BuildTool::Make, BuildTool::CMake],
+            Language::CSharp => &[BuildTool::Make, BuildTool::MSBuild],
+        }
+    }
+    pub fn default_build_tool(&self) -> BuildTool {
+        self.build_tools()[0]
+    }
+    pub fn supports(&self, build_tool: BuildTool) -> bool {
+        self.build_tools().contains(&build_tool)
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Language::Rust => f.write_str("rust"),
+            Language::C => f.write_str("c"),
+            Language::Cpp => f.write_str("cpp"),
+            Language::CSharp => f.write_str("csharp"),
+        }
+    }
+}
+
+impl Display for BuildTool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildTool::Cargo => f.write_str("cargo"),
+            BuildTool::Make => f.write_str("make"),
+            BuildTool::CMake => f.write_str("cmake"),
+            BuildTool::MSBuild => f.write_str("msbuild"),
+        }
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! cargo (Cargo.toml) backend
+//!
+//! + ![note] the profile `debug` is cargo's `dev` profile, other profiles are passed as is, and
+//!   defined by `--config profile.<name>.*` (inheriting `dev` or `release`) if needed.
+//! + ![note] the `opt-level` and `debug` of the profile override cargo's, the `env` of the
+//!   profile is passed to cargo, `defines` are passed as `--cfg <define>`.
+//! + ![note] the `--cfg` flags are appended to `CARGO_ENCODED_RUSTFLAGS` or `RUSTFLAGS` if they
+//!   are set (by the environment or the `env` of the profile), otherwise they are merged into
+//!   `build.rustflags` of the cargo config, which is ignored if `target.<triple>.rustflags` is
+//!   set.
+//! + ![note] the libraries are the `staticlib` and `cdylib` targets.
+//! + ![note] cargo builds into `target/<profile>/build/<project>`, and the binaries are copied
+//!   into `target/<profile>`.
+//! + ![note] the files listed by cargo's dep-info files (`*.d`, e.g. the sources of path
+//!   dependencies, files included by `include_str!`), their manifests and the
+//!   `.cargo/config.toml` files are fingerprinted, so cargo is not run if they were not changed.
+//! + ![note] with `--target`, the target is passed to cargo, and the build fails if the rust
+//!   standard library of the target is not installed.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::build::{Artifact, Backend, Context, Error, Result};
+use crate::lang::BuildTool;
+use crate::message::{Event, Level, Stream};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE_NAME: &str = "Cargo.toml";
+/// the tool of [`Error::TargetNotInstalledError`] if the standard library of the target is not
+/// installed
+pub const RUST_STD_NAME: &str = "the rust standard library";
+
+/// the cargo backend
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cargo;
+
+/// a message of `cargo build --message-format json`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum JsonMessage {
+    CompilerArtifact {
+        target: Target,
+        #[serde(default)]
+        filenames: Vec<PathBuf>,
+        #[serde(default)]
+        executable: Option<PathBuf>,
+        #[serde(default)]
+        fresh: bool,
+    },
+    CompilerMessage {
+        message: Diagnostic,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    name: String,
+    #[serde(default)]
+    kind: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diagnostic {
+    #[serde(default)]
+    level: String,
+    #[serde(default)]
+    rendered: Option<String>,
+}
+
+impl Diagnostic {
+    /// the level of the diagnostic (`note` and `help` are infos)
+    fn level(&self) -> Level {
+        match self.level.as_str() {
+            level if level.starts_with("error") => Level::Error,
+            "warning" => Level::Warning,
+            _ => Level::Info,
+        }
+    }
+}
+
+impl Backend for Cargo {
+    fn build(&self, context: &Context, binary: Option<&str>) -> Result<Vec<Artifact>> {
+        let mut command = context.command("cargo");
+        // rustup picks the toolchain (`rust-toolchain.toml`), and cargo its config
+        // (`.cargo/config.toml`), from the working directory
+        command
+            .current_dir(&context.project.dir)
+            .arg("build")
+            .arg("--manifest-path")
+            .arg(context.project.dir.join(MANIFEST_FILE_NAME))
+            .arg("--target-dir")
+            .arg(context.build_dir())
+            .arg("--message-format")
+            .arg(if colored::control::SHOULD_COLORIZE.should_colorize() {
+                "json-diagnostic-rendered-ansi"
+            } else {
+                "json"
+            })
+            .arg("--quiet");
+        let profile = &context.settings;
+        let cargo_profile = cargo_profile(context);
+        command.arg("--profile").arg(cargo_profile);
+        if !matches!(cargo_profile, "dev" | "release") {
+            command.arg("--config").arg(format!(
+                "profile.{}.inherits=\"{}\"",
+                cargo_profile,
+                if profile.opt_level == 0 {
+                    "dev"
+                } else {
+                    "release"
+                }
+            ));
+        }
+        command
+            .arg("--config")
+            .arg(format!(
+                "profile.{}.opt-level={}",
+                cargo_profile, profile.opt_level
+            ))
+            .arg("--config")
+            .arg(format!("profile.{}.debug={}", cargo_profile, profile.debug));
+        if !profile.defines.is_empty() {
+            let cfgs = profile.defines.iter().map(|define| cfg(define));
+            // the environment variables override the `rustflags` of the cargo config
+            if let Some(mut rust_flags) = context.env("CARGO_ENCODED_RUSTFLAGS") {
+                for cfg in cfgs {
+                    if !rust_flags.is_empty() {
+                        rust_flags.push('\x1f');
+                    }
+                    rust_flags.push_str("--cfg\x1f");
+                    rust_flags.push_str(&cfg);
+                }
+                command.env("CARGO_ENCODED_RUSTFLAGS", rust_flags);
+            } else if let Some(mut rust_flags) = context.env("RUSTFLAGS") {
+                for cfg in cfgs {
+                    rust_flags.push_str(" --cfg ");
+                    rust_flags.push_str(&cfg);
+                }
+                command.env("RUSTFLAGS", rust_flags.trim_start());
+            } else {
+                let rust_flags = cfgs
+                    .flat_map(|cfg| ["--cfg".to_owned(), cfg])
+                    .collect::<Vec<_>>();
+                command
+                    .arg("--config")
+                    .arg(format!("build.rustflags={}", toml::Value::from(rust_flags)));
+            }
+        }
+        if let Some(binary) = binary {
+            command.arg("--bin").arg(binary);
+        }
+        if let Some(target) = context.target {
+            command.arg("--target").arg(&target.triple);
+            if let Some(linker) = &target.linker {
+                command.env(
+                    format!(
+                        "CARGO_TARGET_{}_LINKER",
+                        crate::build::env_name(&target.triple)
+                    ),
+                    linker,
+                );
+            }
+        }
+
+        let mut executables = Vec::new();
+        let mut libraries = Vec::<PathBuf>::new();
+        let status =
+            crate::build::run_tool(
+                context,
+                &mut command,
+                "cargo",
+                |line| match serde_json::from_str::<JsonMessage>(line) {
+                    Ok(JsonMessage::CompilerArtifact {
+                        target,
+                        filenames,
+                        executable,
+                        fresh,
+                    }) => {
+                        if !fresh {
+                            context
+                                .output
+                                .message(format_args!("compiled '{}'", target.name));
+                        }
+                        if target
+                            .kind
+                            .iter()
+                            .any(|kind| matches!(kind.as_str(), "staticlib" | "cdylib"))
+                        {
+                            libraries.extend(filenames.into_iter().filter(|filename| {
+                                filename.extension().is_some_and(|extension| {
+                                    extension != "rlib" && extension != "rmeta"
+                                })
+                            }));
+                        }
+                        executables.extend(executable);
+                    }
+                    Ok(JsonMessage::CompilerMessage { message }) => {
+                        let level = message.level();
+                        if let Some(rendered) = message.rendered {
+                            context.output.emit(Event::Diagnostic {
+                                project: Some(context.project.name.clone()),
+                                level,
+                                message: rendered,
+                                hint: None,
+                            });
+                        }
+                    }
+                    Ok(JsonMessage::Other) => {}
+                    Err(_) => context.output.line(Stream::Stdout, line),
+                },
+            )?;
+        if !status.success() {
+            return Err(Error::FailedError {
+                project: context.project.name.clone(),
+                tool: BuildTool::Cargo,
+                code: status.code(),
+            });
+        }
+        let mut artifacts = executables
+            .iter()
+            .map(|executable| context.install(executable))
+            .collect::<Result<Vec<_>>>()?;
+        artifacts.extend(libraries.iter().map(|library| context.library(library)));
+        Ok(artifacts)
+    }
+    fn inputs(&self, context: &Context) -> Vec<PathBuf> {
+        let mut out_dir = context.build_dir();
+        if let Some(target) = context.target {
+            out_dir.push(&target.triple);
+        }
+        out_dir.push(match cargo_profile(context) {
+            "dev" => "debug",
+            profile => profile,
+        });
+        let mut inputs = dep_info(&out_dir)
+            .into_iter()
+            .filter(|input| {
+                input.is_absolute()
+                    && !input.starts_with(&context.project.dir)
+                    && !input.starts_with(context.workspace_dir.join(crate::build::TARGET_DIR_NAME))
+            })
+            .collect::<BTreeSet<_>>();
+        // the manifests of the path dependencies are not listed
+        let manifests = inputs
+            .iter()
+            .filter_map(|input| {
+                input
+                    .ancestors()
+                    .map(|dir| dir.join(MANIFEST_FILE_NAME))
+                    .find(|manifest| manifest.is_file())
+            })
+            .collect::<Vec<_>>();
+        inputs.extend(manifests);
+        // the hidden `.cargo` directory of the project is not fingerprinted
+        for dir in context.project.dir.ancestors() {
+            for name in ["config.toml", "config"] {
+                let config = dir.join(".cargo").join(name);
+                if config.is_file() {
+                    inputs.insert(config);
+                }
+            }
+        }
+        inputs.into_iter().collect()
+    }
+    fn toolchain(&self, context: &Context) -> Result<Vec<String>> {
+        let mut toolchain = vec![
+            crate::build::tool_version(context, "cargo", &[])?,
+            crate::build::tool_version(context, "rustc", &[])?,
+        ];
+        if let Some(target) = context.target {
+            let output = context
+                .command("rustc")
+                .current_dir(&context.project.dir)
+                .args(["--print", "target-libdir", "--target", &target.triple])
+                .output()
+                .map_err(|e| Error::IOError(PathBuf::from("rustc"), e))?;
+            let libdir = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+            if !output.status.success() || !Path::new(&libdir).is_dir() {
+                return Err(Error::TargetNotInstalledError {
+                    target: target.triple.clone(),
+                    tool: RUST_STD_NAME.to_owned(),
+                });
+            }
+            if let Some(linker) = &target.linker {
+                toolchain.push(crate::build::compiler_version(context, linker)?);
+            }
+        }
+        Ok(toolchain)
+    }
+}
+
+/// the name of the cargo profile of the profile (`debug` is `dev`)
+fn cargo_profile<'a>(context: &Context<'a>) -> &'a str {
+    match context.profile {
+        crate::build::DEFAULT_PROFILE => "dev",
+        profile => profile,
+    }
+}
+
+/// the files listed by the dep-info files (`*.d`) in `dir`
+fn dep_info(dir: &Path) -> BTreeSet<PathBuf> {
+    let mut inputs = BTreeSet::new();
+    for path in crate::fs::sorted_entries(dir).unwrap_or_default() {
+        if path.extension().is_none_or(|extension| extension != "d") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        for line in content.lines() {
+            let Some((_, dependencies)) = line.split_once(": ") else {
+                continue;
+            };
+            // the dependencies are separated by spaces, and `\ ` is an escaped space
+            let mut dependency = String::new();
+            let mut chars = dependencies.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' if chars.peek() == Some(&' ') => dependency.push(chars.next().unwrap()),
+                    ' ' => {
+                        if !dependency.is_empty() {
+                            inputs.insert(PathBuf::from(std::mem::take(&mut dependency)));
+                        }
+                    }
+                    c => dependency.push(c),
+                }
+            }
+            if !dependency.is_empty() {
+                inputs.insert(PathBuf::from(dependency));
+            }
+        }
+    }
+    inputs
+}
+
+/// the `--cfg` of the define `NAME` or `NAME=VALUE` (as `NAME="VALUE"`)
+fn cfg(define: &str) -> String {
+    match define.split_once('=') {
+        Some((name, value)) => format!("{}=\"{}\"", name, value.trim_matches('"')),
+        None => define.to_owned(),
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! cmake (CMakeLists.txt) backend
+//!
+//! > 1. configure: `cmake -S <project> -B target/<profile>/build/<project> -D...`
+//! >    + ![note] skipped if the cmake files and the profile are not changed since the last
+//! >      configure.
+//! > 2. build: `cmake --build target/<profile>/build/<project>`
+//! > 3. the executable targets are read from the cmake file api, and copied into
+//! >    `target/<profile>`, the library targets are the libraries of the project.
+//!
+//! > the cache variables:
+//! >
+//! > - `CMAKE_BUILD_TYPE`: `Debug` if the optimization level of the profile is 0, otherwise
+//! >   `RelWithDebInfo` with debug info, or `Release`.
+//! > - `CMAKE_C_FLAGS`, `CMAKE_CXX_FLAGS`: the defines of the profile (`-D<define>...`), empty
+//! >   without defines, so removed defines do not stay in the cache.
+//! > - `CMAKE_C_COMPILER`, `CMAKE_CXX_COMPILER`: the compilers of the target (with `--target`).
+//! > - the variables in `[profile.<name>.cmake]`.
+//! >   + ![note] the variables removed since the last configure are removed from the cache
+//! >     (`-U<name>`).
+//!
+//! + ![note] the `env` of the profile is passed to cmake.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::build::{Artifact, Backend, Context, Error, Result};
+use crate::lang::BuildTool;
+use crate::message::Stream;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const CMAKE_LISTS_FILE_NAME: &str = "CMakeLists.txt";
+/// the hash of the inputs of the last configure
+const STAMP_FILE_NAME: &str = "abuild-configure.stamp";
+/// the names of the `[profile.<name>.cmake]` variables of the last configure, one per line
+const VARIABLES_FILE_NAME: &str = "abuild-configure.variables";
+
+/// the cmake backend
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CMake;
+
+#[derive(Debug, Deserialize)]
+struct Index {
+    reply: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Reference {
+    #[serde(rename = "jsonFile")]
+    json_file: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct CodeModel {
+    configurations: Vec<Configuration>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Configuration {
+    name: String,
+    targets: Vec<TargetReference>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TargetReference {
+    name: String,
+    #[serde(rename = "jsonFile")]
+    json_file: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    artifacts: Vec<TargetArtifact>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TargetArtifact {
+    path: PathBuf,
+}
+
+impl Backend for CMake {
+    fn build(&self, context: &Context, binary: Option<&str>) -> Result<Vec<Artifact>> {
+        let build_dir = context.build_dir();
+        let build_type = build_type(context);
+        let mut arguments = vec![format!("-DCMAKE_BUILD_TYPE={}", build_type)];
+        let defines = context
+            .settings
+            .defines
+            .iter()
+            .map(|define| format!("-D{}", define))
+            .collect::<Vec<_>>()
+            .join(" ");
+        arguments.push(format!("-DCMAKE_C_FLAGS={}", defines));
+        arguments.push(format!("-DCMAKE_CXX_FLAGS={}", defines));
+        if let Some(target) = context.target {
+            arguments.push(format!("-DCMAKE_C_COMPILER={}", target.cc));
+            arguments.push(format!("-DCMAKE_CXX_COMPILER={}", target.cxx));
+        }
+        arguments.extend(
+            context
+                .settings
+                .cmake
+                .iter()
+                .map(|(name, value)| format!("-D{}={}", name, value)),
+        );
+        configure(context, &build_dir, &arguments)?;
+
+        let mut command = context.command("cmake");
+        command
+            .arg("--build")
+            .arg(&build_dir)
+            .arg("--config")
+            .arg(&build_type);
+        if let Some(binary) = binary {
+            command.arg("--target").arg(binary);
+        }
+        let status = crate::build::run_tool(context, &mut command, "cmake", |line| {
+            context.output.line(Stream::Stdout, line);
+        })?;
+        if !status.success() {
+            return Err(Error::FailedError {
+                project: context.project.name.clone(),
+                tool: BuildTool::CMake,
+                code: status.code(),
+            });
+        }
+
+        let targets = targets(&build_dir, &build_type)?;
+        let executables = targets
+            .iter()
+            .filter(|(name, (kind, _))| {
+                kind == "EXECUTABLE" && binary.is_none_or(|binary| *name == binary)
+            })
+            .flat_map(|(_, (_, paths))| paths)
+            .collect::<Vec<_>>();
+        if let (Some(binary), true) = (binary, executables.is_empty()) {
+            return Err(Error::BinaryNotFoundError {
+                project: context.project.name.clone(),
+                binary: binary.to_owned(),
+            });
+        }
+        let mut artifacts = executables
+            .iter()
+            .map(|path| context.install(path))
+            .collect::<Result<Vec<_>>>()?;
+        artifacts.extend(
+            targets
+                .values()
+                .filter(|(kind, _)| kind.ends_with("_LIBRARY"))
+                .flat_map(|(_, paths)| paths)
+                .map(|path| context.library(path)),
+        );
+        Ok(artifacts)
+    }
+    fn toolchain(&self, context: &Context) -> Result<Vec<String>> {
+        let mut toolchain = vec![crate::build::tool_version(context, "cmake", &[])?];
+        if let Some(target) = context.target {
+            toolchain.push(crate::build::compiler_version(context, &target.cc)?);
+            toolchain.push(crate::build::compiler_version(context, &target.cxx)?);
+        }
+        Ok(toolchain)
+    }
+}
+
+/// the `CMAKE_BUILD_TYPE` of the profile
+fn build_type(context: &Context) -> String {
+    if let Some(build_type) = context.settings.cmake.get("CMAKE_BUILD_TYPE") {
+        return build_type.clone();
+    }
+    match (context.settings.opt_level, context.settings.debug) {
+        (0, _) => "Debug".to_owned(),
+        (_, true) => "RelWithDebInfo".to_owned(),
+        _ => "Release".to_owned(),
+    }
+}
+
+/// run the configure step if the cmake files or the arguments were changed since the last
+/// configure
+fn configure(context: &Context, build_dir: &Path, arguments: &[String]) -> Result<()> {
+    let project_dir = &context.project.dir;
+    let stamp = stamp(project_dir, arguments)?;
+    let stamp_file = build_dir.join(STAMP_FILE_NAME);
+    if build_dir.join("CMakeCache.txt").is_file()
+        && reply_dir(build_dir).is_dir()
+        && std::fs::read_to_string(&stamp_file).is_ok_and(|previous| previous == stamp)
+    {
+        context.output.message("configure is up to date.");
+        return Ok(());
+    }
+
+    let query_file = build_dir.join(".cmake/api/v1/query/codemodel-v2");
+    if let Some(query_dir) = query_file.parent() {
+        std::fs::create_dir_all(query_dir)
+            .map_err(|e| Error::IOError(query_dir.to_path_buf(), e))?;
+    }
+    std::fs::write(&query_file, "").map_err(|e| Error::IOError(query_file.clone(), e))?;
+
+    // the cache keeps a variable until it is removed, even if it is not passed any more
+    let variables_file = build_dir.join(VARIABLES_FILE_NAME);
+    let previous = std::fs::read_to_string(&variables_file).unwrap_or_default();
+    let removed = previous
+        .lines()
+        .filter(|name| {
+            !arguments
+                .iter()
+                .any(|argument| argument.starts_with(&format!("-D{}=", name)))
+        })
+        .map(|name| format!("-U{}", name));
+
+    let mut command = context.command("cmake");
+    command
+        .arg("-S")
+        .arg(project_dir)
+        .arg("-B")
+        .arg(build_dir)
+        .args(arguments)
+        .args(removed);
+    let status = crate::build::run_tool(context, &mut command, "cmake", |line| {
+        context.output.line(Stream::Stdout, line);
+    })?;
+    if !status.success() {
+        return Err(Error::FailedError {
+            project: context.project.name.clone(),
+            tool: BuildTool::CMake,
+            code: status.code(),
+        });
+    }
+    let variables = context
+        .settings
+        .cmake
+        .keys()
+        .map(|name| format!("{}\n", name))
+        .collect::<String>();
+    std::fs::write(&variables_file, variables)
+        .map_err(|e| Error::IOError(variables_file.clone(), e))?;
+    std::fs::write(&stamp_file, stamp).map_err(|e| Error::IOError(stamp_file.clone(), e))
+}
+
+/// the hash of the cmake files (`CMakeLists.txt`, `*.cmake`) of the project and the arguments
+fn stamp(project_dir: &Path, arguments: &[String]) -> Result<String> {
+    fn visit(dir: &Path, root: &Path, hasher: &mut Sha256) -> Result<()> {
+        let entries =
+            crate::fs::sorted_entries(dir).map_err(|e| Error::IOError(dir.to_path_buf(), e))?;
+        for path in entries {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if name.starts_with('.') || name == crate::build::TARGET_DIR_NAME {
+                continue;
+            }
+            if path.is_dir() {
+                visit(&path, root, hasher)?;
+            } else if name == CMAKE_LISTS_FILE_NAME || name.ends_with(".cmake") {
+                let content = std::fs::read(&path).map_err(|e| Error::IOError(path.clone(), e))?;
+                let relative = path.strip_prefix(root).unwrap_or(&path);
+                hasher.update(relative.as_os_str().as_encoded_bytes());
+                hasher.update((content.len() as u64).to_le_bytes());
+                hasher.update(&content);
+            }
+        }
+        Ok(())
+    }
+    let mut hasher = Sha256::new();
+    visit(project_dir, project_dir, &mut hasher)?;
+    for argument in arguments {
+        hasher.update((argument.len() as u64).to_le_bytes());
+        hasher.update(argument.as_bytes());
+    }
+    Ok(crate::fs::hex(&hasher.finalize()))
+}
+
+fn reply_dir(build_dir: &Path) -> PathBuf {
+    build_dir.join(".cmake/api/v1/reply")
+}
+
+/// the targets, their types (e.g. `EXECUTABLE`) and artifacts, read from the cmake file api
+fn targets(build_dir: &Path, build_type: &str) -> Result<BTreeMap<String, (String, Vec<PathBuf>)>> {
+    let reply_dir = reply_dir(build_dir);
+    let index_file = crate::fs::sorted_entries(&reply_dir)
+        .map_err(|e| Error::IOError(reply_dir.clone(), e))?
+        .into_iter()
+        .rfind(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("index-"))
+        })
+        .ok_or_else(|| {
+            Error::IOError(
+                reply_dir.clone(),
+                std::io::Error::new(std::io::ErrorKind::NotFound, "no cmake file api reply"),
+            )
+        })?;
+    let index: Index = read_json(&index_file)?;
+    let code_model = index
+        .reply
+        .get("codemodel-v2")
+        .cloned()
+        .and_then(|reference| serde_json::from_value::<Reference>(reference).ok())
+        .ok_or_else(|| {
+            Error::IOError(
+                index_file.clone(),
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "no codemodel-v2 reply"),
+            )
+        })?;
+    let code_model: CodeModel = read_json(&reply_dir.join(code_model.json_file))?;
+    let Some(configuration) = code_model
+        .configurations
+        .iter()
+        .find(|configuration| configuration.name == build_type)
+        .or(code_model.configurations.first())
+    else {
+        return Ok(BTreeMap::new());
+    };
+    let mut targets = BTreeMap::new();
+    for target in &configuration.targets {
+        let details: Target = read_json(&reply_dir.join(&target.json_file))?;
+        let artifacts = details
+            .artifacts
+            .into_iter()
+            .map(|artifact| build_dir.join(artifact.path))
+            .filter(|path| path.is_file())
+            .collect();
+        targets.insert(target.name.clone(), (details.kind, artifacts));
+    }
+    Ok(targets)
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let content = std::fs::read(path).map_err(|e| Error::IOError(path.to_path_buf(), e))?;
+    serde_json::from_slice(&content)
+        .map_err(|e| Error::IOError(path.to_path_buf(), std::io::Error::other(e)))
+}
@@ -0,0 +1,250 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! make (Makefile) backend
+//!
+//! > the variables passed to make (as environment variables, so the makefile can extend them,
+//! > e.g. `CFLAGS += -Iinclude`):
+//! >
+//! > - `CC`, `CXX`: the c/c++ compilers (default: the `env` of the profile, the compilers of the
+//! >   target with `--target`, `$CC`, `$CXX`, or `cc`, `c++`)
+//! > - `CFLAGS`, `CXXFLAGS`: the flags of the `env` of the profile (or `$CFLAGS`, `$CXXFLAGS`),
+//! >   followed by the flags of the profile (`-O<opt-level> [-g] -D<define>...`)
+//! > - `OUT_DIR`: the build directory (`target/<profile>/build/<project>`)
+//!
+//! + ![note] the `env` of the profile is passed to make.
+//! + ![note] the binaries are the executables in `OUT_DIR`, and the executables updated by make
+//!   in the project directory (for makefiles which ignore `OUT_DIR`), which are remembered for
+//!   the next builds. other executables (e.g. `configure`) are never binaries, so a makefile
+//!   which ignores `OUT_DIR` and has nothing to do finds no binaries.
+//! + ![note] the libraries are the `*.a`, `*.so`, `*.dylib`, `*.lib` and `*.dll` in `OUT_DIR`.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::build::{Artifact, Backend, Context, Error, Result};
+use crate::lang::{BuildTool, Language};
+use crate::message::Stream;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const MAKEFILE_NAME: &str = "Makefile";
+/// the binaries found by the previous builds, relative to the project directory
+const ARTIFACTS_FILE_NAME: &str = "abuild-artifacts.txt";
+
+/// the make backend
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Make;
+
+impl Backend for Make {
+    fn build(&self, context: &Context, binary: Option<&str>) -> Result<Vec<Artifact>> {
+        let project_dir = &context.project.dir;
+        let out_dir = context.build_dir();
+        std::fs::create_dir_all(&out_dir).map_err(|e| Error::IOError(out_dir.clone(), e))?;
+        let out_dir = out_dir
+            .canonicalize()
+            .map_err(|e| Error::IOError(out_dir.clone(), e))?;
+        let before = executables(project_dir)?;
+
+        let mut command = context.command("make");
+        command.current_dir(project_dir);
+        // not `make NAME=value`, which would override the assignments of the makefile
+        command.envs(variables(context, &out_dir));
+        let status = crate::build::run_tool(context, &mut command, "make", |line| {
+            context.output.line(Stream::Stdout, line);
+        })?;
+        if !status.success() {
+            return Err(Error::FailedError {
+                project: context.project.name.clone(),
+                tool: BuildTool::Make,
+                code: status.code(),
+            });
+        }
+
+        let mut found = executables(&out_dir)?.into_keys().collect::<BTreeSet<_>>();
+        let after = executables(project_dir)?;
+        for (path, modified) in &after {
+            if before.get(path) != Some(modified) {
+                found.insert(path.clone());
+            }
+        }
+        let artifacts_file = out_dir.join(ARTIFACTS_FILE_NAME);
+        if let Ok(previous) = std::fs::read_to_string(&artifacts_file) {
+            found.extend(
+                previous
+                    .lines()
+                    .map(|line| project_dir.join(line))
+                    .filter(|path| path.is_file()),
+            );
+        }
+        std::fs::write(
+            &artifacts_file,
+            found
+                .iter()
+                .filter_map(|path| path.strip_prefix(project_dir).ok())
+                .map(|path| path.to_string_lossy() + "\n")
+                .collect::<String>(),
+        )
+        .map_err(|e| Error::IOError(artifacts_file.clone(), e))?;
+
+        let found = found
+            .into_iter()
+            .filter(|path| {
+                binary.is_none_or(|binary| path.file_stem().is_some_and(|stem| stem == binary))
+            })
+            .collect::<Vec<_>>();
+        if let (Some(binary), true) = (binary, found.is_empty()) {
+            return Err(Error::BinaryNotFoundError {
+                project: context.project.name.clone(),
+                binary: binary.to_owned(),
+            });
+        }
+        let mut artifacts = found
+            .iter()
+            .map(|path| context.install(path))
+            .collect::<Result<Vec<_>>>()?;
+        artifacts.extend(
+            libraries(&out_dir)?
+                .iter()
+                .map(|path| context.library(path)),
+        );
+        Ok(artifacts)
+    }
+    fn toolchain(&self, context: &Context) -> Result<Vec<String>> {
+        let mut toolchain = vec![crate::build::tool_version(context, "make", &[])?];
+        let compiler = match context.project.config.config.language {
+            Language::C => Some("CC"),
+            Language::Cpp => Some("CXX"),
+            _ => None,
+        };
+        if let Some(compiler) = compiler {
+            let variables = variables(context, Path::new(""));
+            toolchain.push(crate::build::compiler_version(
+                context,
+                &variables[compiler],
+            )?);
+        }
+        Ok(toolchain)
+    }
+}
+
+/// the variables passed to make
+fn variables(context: &Context, out_dir: &Path) -> BTreeMap<&'static str, String> {
+    let mut variables = BTreeMap::new();
+    if let Language::C | Language::Cpp = context.project.config.config.language {
+        let compiler = |name, target: Option<&String>, default: &str| match (
+            context.settings.env.get(name),
+            target,
+        ) {
+            (Some(compiler), _) | (None, Some(compiler)) => compiler.clone(),
+            (None, None) => std::env::var(name).unwrap_or_else(|_| default.to_owned()),
+        };
+        variables.insert("CC", compiler("CC", context.target.map(|t| &t.cc), "cc"));
+        variables.insert(
+            "CXX",
+            compiler("CXX", context.target.map(|t| &t.cxx), "c++"),
+        );
+        let flags = context.settings.c_flags();
+        for name in ["CFLAGS", "CXXFLAGS"] {
+            let value = match context.env(name) {
+                Some(value) if !value.trim().is_empty() => format!("{} {}", value.trim(), flags),
+                _ => flags.clone(),
+            };
+            variables.insert(name, value);
+        }
+    }
+    variables.insert("OUT_DIR", out_dir.to_string_lossy().into_owned());
+    variables
+}
+
+/// the executables in `dir` (recursively, hidden directories and symbolic links are skipped) and
+/// their modification times
+fn executables(dir: &Path) -> Result<BTreeMap<PathBuf, SystemTime>> {
+    fn visit(dir: &Path, found: &mut BTreeMap<PathBuf, SystemTime>) -> Result<()> {
+        let entries =
+            crate::fs::sorted_entries(dir).map_err(|e| Error::IOError(dir.to_path_buf(), e))?;
+        for path in entries {
+            let metadata =
+                std::fs::symlink_metadata(&path).map_err(|e| Error::IOError(path.clone(), e))?;
+            // symbolic links may dangle, or point out of the directory
+            if metadata.is_symlink() {
+                continue;
+            }
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if metadata.is_dir() {
+                if !hidden
+                    && path
+                        .file_name()
+                        .is_none_or(|name| name != crate::build::TARGET_DIR_NAME)
+                {
+                    visit(&path, found)?;
+                }
+            } else if !hidden && is_executable(&path, &metadata) {
+                let modified = metadata
+                    .modified()
+                    .map_err(|e| Error::IOError(path.clone(), e))?;
+                found.insert(path, modified);
+            }
+        }
+        Ok(())
+    }
+    let mut found = BTreeMap::new();
+    visit(dir, &mut found)?;
+    Ok(found)
+}
+
+/// the libraries in `dir` (not recursively)
+fn libraries(dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(crate::fs::sorted_entries(dir)
+        .map_err(|e| Error::IOError(dir.to_path_buf(), e))?
+        .into_iter()
+        .filter(|path| path.is_file() && is_library(path))
+        .collect())
+}
+
+/// whether `path` is a static or shared library
+fn is_library(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        matches!(
+            extension.to_string_lossy().to_lowercase().as_str(),
+            "a" | "so" | "dylib" | "lib" | "dll"
+        )
+    })
+}
+
+/// whether `path` is an executable (not a library or an object file)
+pub fn is_executable(path: &Path, metadata: &std::fs::Metadata) -> bool {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("exe") => true,
+        Some("so" | "dylib" | "dll" | "a" | "lib" | "o" | "obj" | "sh" | "py") => false,
+        _ => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                metadata.permissions().mode() & 0o111 != 0
+            }
+            #[cfg(not(unix))]
+            {
+                let _ = metadata;
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//!
+//! msbuild (*.csproj) backend, run by the .NET SDK (`dotnet`)
+//!
+//! > `dotnet publish <project>.csproj --artifacts-path target/<profile>/build/<project> --output
+//! > target/<profile>/build/<project>/publish -p:...`, the properties:
+//! >
+//! > - `Configuration`: `Debug` if the optimization level of the profile is 0, otherwise
+//! >   `Release`
+//! > - `Optimize`: whether the optimization level of the profile is not 0
+//! > - `DebugType`: `portable` with debug info, otherwise `none`
+//! > - `DefineConstants`: `TRACE`, `DEBUG` (for `Debug`) and the names of the defines of the
+//! >   profile (c# defines have no values)
+//! > - `PublishSingleFile`: for executables (`<OutputType>Exe</OutputType>`), so the binary is
+//! >   copied into `target/<profile>` without the assemblies next to it
+//!
+//! + ![note] the project file is the first `*.csproj` in the project directory.
+//! + ![note] the .NET 8 SDK or newer is needed (`--artifacts-path`), so `bin` and `obj` are not
+//!   written into the project directory.
+//! + ![note] the runtime of the host is used, with `--target`, the runtime identifier of the
+//!   target (e.g. `linux-x64` for `x86_64-unknown-linux-gnu`).
+//! + ![note] the `env` of the profile is passed to dotnet.
+//! + ![note] the binaries are the executables in the publish directory, the libraries are the
+//!   `*.dll` in the publish directory of a library project.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::build::{Artifact, Backend, Context, Error, Result};
+use crate::lang::BuildTool;
+use crate::message::Stream;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_FILE_EXTENSION: &str = "csproj";
+/// the directory of the published assemblies in the build directory
+const PUBLISH_DIR_NAME: &str = "publish";
+
+/// the msbuild backend
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MSBuild;
+
+impl Backend for MSBuild {
+    fn build(&self, context: &Context, binary: Option<&str>) -> Result<Vec<Artifact>> {
+        let project_file = project_file(&context.project.dir)?;
+        let content = std::fs::read_to_string(&project_file)
+            .map_err(|e| Error::IOError(project_file.clone(), e))?
+            .to_lowercase();
+        let executable = ["<outputtype>exe<", "<outputtype>winexe<"]
+            .iter()
+            .any(|output_type| content.contains(output_type));
+        let build_dir = context.build_dir();
+        // the assemblies of the previous builds (e.g. of another configuration) are not binaries
+        let publish_dir = build_dir.join(PUBLISH_DIR_NAME);
+        match std::fs::remove_dir_all(&publish_dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                return Err(Error::IOError(publish_dir, e));
+            }
+            _ => {}
+        }
+
+        let profile = &context.settings;
+        let configuration = match profile.opt_level {
+            0 => "Debug",
+            _ => "Release",
+        };
+        let mut defines = vec!["TRACE"];
+        if configuration == "Debug" {
+            defines.push("DEBUG");
+        }
+        defines.extend(profile.defines.iter().map(|define| {
+            define
+                .split_once('=')
+                .map_or(define.as_str(), |(name, _)| name)
+        }));
+        let mut command = context.command("dotnet");
+        command
+            .current_dir(&context.project.dir)
+            .arg("publish")
+            .arg(&project_file)
+            .arg("--nologo")
+            .arg("--artifacts-path")
+            .arg(&build_dir)
+            .arg("--output")
+            .arg(&publish_dir)
+            .arg(format!("-p:Configuration={}", configuration))
+            .arg(format!("-p:Optimize={}", profile.opt_level != 0))
+            .arg(format!(
+                "-p:DebugType={}",
+                if profile.debug { "portable" } else { "none" }
+            ))
+            // `;` separates the properties
+            .arg(format!("-p:DefineConstants={}", defines.join("%3B")));
+        if executable {
+            command
+                .arg("-p:PublishSingleFile=true")
+                .arg("--self-contained")
+                .arg("false");
+        }
+        match context.target {
+            Some(target) => command.arg("--runtime").arg(runtime(&target.triple)),
+            None => command.arg("--use-current-runtime"),
+        };
+        let status = crate::build::run_tool(context, &mut command, "dotnet", |line| {
+            context.output.line(Stream::Stdout, line);
+        })?;
+        if !status.success() {
+            return Err(Error::FailedError {
+                project: context.project.name.clone(),
+                tool: BuildTool::MSBuild,
+                code: status.code(),
+            });
+        }
+
+        let assemblies = crate::fs::sorted_entries(&publish_dir)
+            .map_err(|e| Error::IOError(publish_dir.clone(), e))?;
+        let executables = assemblies
+            .iter()
+            .filter(|path| executable && is_binary(path))
+            .filter(|path| {
+                binary.is_none_or(|binary| path.file_stem().is_some_and(|stem| stem == binary))
+            })
+            .collect::<Vec<_>>();
+        if let (Some(binary), true) = (binary, executables.is_empty()) {
+            return Err(Error::BinaryNotFoundError {
+                project: context.project.name.clone(),
+                binary: binary.to_owned(),
+            });
+        }
+        let mut artifacts = executables
+            .iter()
+            .map(|path| context.install(path))
+            .collect::<Result<Vec<_>>>()?;
+        if !executable {
+            artifacts.extend(
+                assemblies
+                    .iter()
+                    .filter(|path| path.extension().is_some_and(|extension| extension == "dll"))
+                    .map(|path| context.library(path)),
+            );
+        }
+        Ok(artifacts)
+    }
+    fn toolchain(&self, context: &Context) -> Result<Vec<String>> {
+        Ok(vec![crate::build::tool_version(context, "dotnet", &[])?])
+    }
+}
+
+/// the first `*.csproj` in the project directory
+fn project_file(project_dir: &Path) -> Result<PathBuf> {
+    crate::fs::sorted_entries(project_dir)
+        .map_err(|e| Error::IOError(project_dir.to_path_buf(), e))?
+        .into_iter()
+        .find(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension == PROJECT_FILE_EXTENSION)
+        })
+        .ok_or_else(|| {
+            Error::IOError(
+                project_dir.join(format!("*.{}", PROJECT_FILE_EXTENSION)),
+                io::Error::new(io::ErrorKind::NotFound, "no project file"),
+            )
+        })
+}
+
+/// whether the published file `path` is an executable
+fn is_binary(path: &Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|metadata| {
+        metadata.is_file() && crate::lang::make::is_executable(path, &metadata)
+    })
+}
+
+/// the .NET runtime identifier of the target `triple` (e.g. `linux-x64`), the triple itself if
+/// it is not known
+fn runtime(triple: &str) -> String {
+    let arch = match triple.split('-').next().unwrap_or_default() {
+        "x86_64" => "x64",
+        "i586" | "i686" => "x86",
+        "aarch64" => "arm64",
+        arch if arch.starts_with("arm") || arch.starts_with("thumb") => "arm",
+        _ => return triple.to_owned(),
+    };
+    let os = if triple.contains("windows") {
+        "win"
+    } else if triple.contains("apple-darwin") {
+        "osx"
+    } else if triple.contains("android") {
+        "android"
+    } else if triple.contains("linux-musl") {
+        "linux-musl"
+    } else if triple.contains("linux") {
+        "linux"
+    } else {
+        return triple.to_owned();
+    };
+    format!("{}-{}", os, arch)
+}
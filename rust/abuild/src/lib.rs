@@ -44,6 +44,9 @@
 //! > > > > deps:
 //! > > > > > ...
 //! > > > >
+//! > > > > fingerprint:
+//! > > > > > ...
+//! > > > >
 //! > > > > ...
 //! > > > >
 //! > > >
@@ -78,12 +81,31 @@
 use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 
+pub mod build;
+
 pub mod command;
 
+pub mod config;
+
+pub mod fingerprint;
+
+pub mod fs;
+
+pub mod gc;
+
+pub mod journal;
+
 pub mod lang;
 
+pub mod message;
+
+pub mod template;
+
+pub mod trash;
+
 pub const APP_NAME: &str = env!("CARGO_PKG_NAME");
 pub const APP_CONFIG_DIR: &str = concat!("./.", env!("CARGO_PKG_NAME"));
+pub const CONFIG_FILE_NAME: &str = "config.toml";
 
 pub fn app_name() -> Cow<'static, str> {
     fn app_name_from_env() -> Option<Cow<'static, str>> {
@@ -98,19 +120,43 @@ pub fn app_name() -> Cow<'static, str> {
     app_name_from_env().unwrap_or(Cow::Borrowed(APP_NAME))
 }
 
+/// the config directory of the current user (e.g. `~/.config/abuild`)
+pub fn user_config_dir() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            #[cfg(windows)]
+            {
+                PathBuf::from(std::env::var_os("APPDATA")?)
+            }
+            #[cfg(not(windows))]
+            {
+                std::env::home_dir()?.join(".config")
+            }
+        }
+    };
+    Some(config_dir.join(APP_NAME))
+}
+
+/// the config directory of the workspace or project in `dir` (e.g. `.abuild`), it does not depend
+/// on the name of the executable, so a renamed binary still finds it
+pub fn app_config_dir_in(dir: &Path) -> PathBuf {
+    dir.join(format!(".{APP_NAME}"))
+}
+
+/// the config directory of the workspace or project in the current directory (`./.abuild`)
+#[deprecated(note = "use `app_config_dir_in` (or `user_config_dir` for the user config)")]
 pub fn app_config_dir() -> Cow<'static, Path> {
-    fn app_config_dir_from_app_name() -> Option<Cow<'static, Path>> {
-        Some(Cow::Owned(
-            PathBuf::from(".").join(".".to_owned() + &app_name()),
-        ))
-    }
-    app_config_dir_from_app_name().unwrap_or(Cow::Borrowed(Path::new(APP_CONFIG_DIR)))
+    Cow::Owned(app_config_dir_in(Path::new(".")))
 }
 
+/// create the config directory of the workspace or project in the current directory if it does
+/// not exist
+#[deprecated(note = "use `app_config_dir_in` and `std::fs::create_dir_all`")]
 pub fn make_app_config_dir() -> std::io::Result<Cow<'static, Path>> {
-    let app_config_dir = app_config_dir();
+    let app_config_dir = app_config_dir_in(Path::new("."));
     if !app_config_dir.exists() {
         std::fs::create_dir(&app_config_dir)?;
     }
-    Ok(app_config_dir)
+    Ok(Cow::Owned(app_config_dir))
 }
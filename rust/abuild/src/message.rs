@@ -0,0 +1,654 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! the messages of abuild
+//!
+//! > all messages are events, which are printed by the reporter: colored text
+//! > (`--message-format human`, the default), or newline-delimited json events on stdout
+//! > (`--message-format json`), other frontends can be plugged in with [`set_reporter`], e.g.
+//! > ```json
+//! > {"version":1,"event":"start","operation":"build"}
+//! > {"version":1,"event":"project","project":"foo","language":"c","build-tool":"make","profile":"debug"}
+//! > {"version":1,"event":"output","project":"foo","stream":"stdout","line":"cc -c foo.c"}
+//! > {"version":1,"event":"artifact","project":"foo","name":"foo","kind":"library","path":"..."}
+//! > {"version":1,"event":"finish","operation":"build"}
+//! > {"version":1,"event":"exit","code":0}
+//! > ```
+//!
+//! > the events (the fields in `[]` are optional):
+//! >
+//! > | event        | fields                                                   |
+//! > |--------------|----------------------------------------------------------|
+//! > | `start`      | `operation` (`build`, `clean`, `gc` or `run`)            |
+//! > | `finish`     | `operation`, [`freed`, `dry-run`] (bytes, clean and gc)  |
+//! > | `project`    | `project`, `language`, `build-tool`, `profile`           |
+//! > | `fresh`      | `project`                                                |
+//! > | `dirty`      | `project`, `reasons`                                     |
+//! > | `output`     | [`project`], [`binary`], `stream`, `line`                |
+//! > | `diagnostic` | [`project`], `level`, `message`, [`hint`]                |
+//! > | `artifact`   | `project`, `name`, `kind`, `path`                        |
+//! > | `removed`    | `path`, `files`, `bytes`, `dry-run`                      |
+//! > | `exited`     | `binary`, [`code`], [`signal`], [`timeout-ms`]           |
+//! > | `value`      | `key`, `value` (get)                                     |
+//! > | `setting`    | `key`, `value`, [`origin`] (config show)                 |
+//! > | `message`    | [`project`], `message`                                   |
+//! > | `command`    | `project`, `command` (`-v`)                              |
+//! > | `exit`       | `code` (the exit code of abuild, always the last event)  |
+//!
+//! > the verbosity of the messages:
+//! >
+//! > - `-q, --quiet`: only warnings, errors, the output of the binaries and the values of
+//! >   `get` and `config show`.
+//! > - default: the progress of the operations and the output of the build tools too.
+//! > - `-v`: the command lines of the build tools too.
+//! > - `-vv`: why each project was built too (as `--explain`).
+//!
+//! + ![note] every event has the `version` of the schema ([`SCHEMA_VERSION`]), which is
+//!   increased if a field is removed or changed, new events and fields may be added in the
+//!   same version.
+//! + ![note] the output of the build tools and the binaries (`abuild run`) is an `output` event
+//!   per line, the diagnostics of the compilers are `diagnostic` events with the project.
+//! + ![note] the colors are enabled with `--color auto` (the default) if stdout is a terminal
+//!   and `NO_COLOR` is not set, and disabled with `--message-format json`.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::build::ArtifactKind;
+use crate::lang::{BuildTool, Language};
+use colored::Colorize;
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// the version of the schema of the json events
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// whether the messages are json events
+static JSON: AtomicBool = AtomicBool::new(false);
+/// the verbosity of the messages
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+/// the reporter of the messages (default: [`HumanReporter`])
+static REPORTER: RwLock<Option<Arc<dyn Reporter>>> = RwLock::new(None);
+
+/// the format of the messages
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum MessageFormat {
+    /// colored text
+    #[default]
+    Human,
+    /// newline-delimited json events on stdout
+    Json,
+}
+
+/// when to use colors
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// if stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// the verbosity of the messages, an event is printed if its verbosity is not greater
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+    VeryVerbose,
+}
+
+/// a frontend which prints the events
+pub trait Reporter: Send + Sync {
+    fn report(&self, event: &Event);
+}
+
+/// the reporter of `--message-format human`
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HumanReporter;
+
+/// the reporter of `--message-format json`
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonReporter;
+
+/// an operation which is started and finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    Build,
+    Clean,
+    Gc,
+    Run,
+}
+
+/// the stream a line of output was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// the level of a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+/// an event of abuild
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(
+    tag = "event",
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case"
+)]
+pub enum Event {
+    Start {
+        operation: Operation,
+    },
+    Finish {
+        operation: Operation,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        freed: Option<u64>,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        dry_run: bool,
+    },
+    Project {
+        project: String,
+        language: Language,
+        build_tool: BuildTool,
+        profile: String,
+    },
+    Fresh {
+        project: String,
+    },
+    /// `reasons` are only printed in human format if they are explained (`--explain`)
+    Dirty {
+        project: String,
+        reasons: Vec<String>,
+    },
+    Output {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        project: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        binary: Option<String>,
+        stream: Stream,
+        line: String,
+    },
+    /// a diagnostic of abuild, or of a compiler (with the project)
+    Diagnostic {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        project: Option<String>,
+        level: Level,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        hint: Option<String>,
+    },
+    Artifact {
+        project: String,
+        name: String,
+        kind: ArtifactKind,
+        path: PathBuf,
+    },
+    Removed {
+        path: PathBuf,
+        files: u64,
+        bytes: u64,
+        dry_run: bool,
+    },
+    /// a binary is exited with `code`, terminated by `signal`, or killed after `timeout_ms`
+    Exited {
+        binary: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        code: Option<i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        signal: Option<i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u64>,
+    },
+    /// the value of a key in a config file
+    Value {
+        key: String,
+        value: toml::Value,
+    },
+    /// an effective value of the config, and the layer it comes from (`--origin`)
+    Setting {
+        key: String,
+        value: toml::Value,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        origin: Option<String>,
+    },
+    Message {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        project: Option<String>,
+        message: String,
+    },
+    /// the command line of a build tool
+    Command {
+        project: String,
+        command: String,
+    },
+    Exit {
+        code: u8,
+    },
+}
+
+/// an event with the version of the schema
+#[derive(Serialize)]
+struct Versioned<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// set the format of the messages, which selects the reporter unless one was set with
+/// [`set_reporter`], the colors are disabled for json
+pub fn set_format(format: MessageFormat) {
+    JSON.store(format == MessageFormat::Json, Ordering::Relaxed);
+    if format == MessageFormat::Json {
+        colored::control::set_override(false);
+    }
+}
+
+/// the format of the messages
+pub fn format() -> MessageFormat {
+    match JSON.load(Ordering::Relaxed) {
+        true => MessageFormat::Json,
+        false => MessageFormat::Human,
+    }
+}
+
+/// set the reporter which prints the events instead of the reporter of the format
+pub fn set_reporter(reporter: impl Reporter + 'static) {
+    *REPORTER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(reporter));
+}
+
+/// set the verbosity of the messages
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+/// the verbosity of the messages
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        2 => Verbosity::Verbose,
+        _ => Verbosity::VeryVerbose,
+    }
+}
+
+/// set when to use colors, `NO_COLOR` disables the colors of `auto`
+pub fn set_color(color: ColorChoice) {
+    let enabled = match color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                && std::io::stdout().is_terminal()
+        }
+    };
+    colored::control::set_override(enabled);
+}
+
+/// pass the event to the reporter, if it is not more verbose than the verbosity
+pub fn emit(event: &Event) {
+    if event.verbosity() > verbosity() {
+        return;
+    }
+    let reporter = REPORTER.read().unwrap_or_else(|e| e.into_inner()).clone();
+    match (reporter, format()) {
+        (Some(reporter), _) => reporter.report(event),
+        (None, MessageFormat::Human) => HumanReporter.report(event),
+        (None, MessageFormat::Json) => JsonReporter.report(event),
+    }
+}
+
+/// print a free text message
+pub fn message(message: impl std::fmt::Display) {
+    emit(&Event::Message {
+        project: None,
+        message: message.to_string(),
+    });
+}
+
+/// print a diagnostic of abuild
+pub fn diagnostic(level: Level, message: impl std::fmt::Display) {
+    emit(&Event::Diagnostic {
+        project: None,
+        level,
+        message: message.to_string(),
+        hint: None,
+    });
+}
+
+impl Operation {
+    /// the progressive form of the operation (e.g. `building`)
+    fn verb(self) -> &'static str {
+        match self {
+            Operation::Build => "building",
+            Operation::Clean => "cleaning",
+            Operation::Gc => "collecting garbage",
+            Operation::Run => "running",
+        }
+    }
+}
+
+impl Reporter for HumanReporter {
+    fn report(&self, event: &Event) {
+        // e.g. stdout is closed by `abuild build | head`
+        let _ = event.write(&mut std::io::stdout().lock(), &mut std::io::stderr().lock());
+    }
+}
+
+impl Reporter for JsonReporter {
+    fn report(&self, event: &Event) {
+        let versioned = Versioned {
+            version: SCHEMA_VERSION,
+            event,
+        };
+        if let Ok(json) = serde_json::to_string(&versioned) {
+            let _ = writeln!(std::io::stdout().lock(), "{}", json);
+        }
+    }
+}
+
+impl Event {
+    /// the least verbosity which prints the event
+    pub fn verbosity(&self) -> Verbosity {
+        match self {
+            Event::Diagnostic {
+                level: Level::Warning | Level::Error,
+                ..
+            }
+            | Event::Output {
+                stream: Stream::Stderr,
+                ..
+            }
+            | Event::Output { project: None, .. }
+            | Event::Exited {
+                timeout_ms: Some(_),
+                ..
+            }
+            | Event::Value { .. }
+            | Event::Setting { .. }
+            | Event::Exit { .. } => Verbosity::Quiet,
+            Event::Command { .. } => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        }
+    }
+    /// write the event as text to `out` (stdout) or `err` (stderr)
+    pub fn write(&self, out: &mut dyn Write, err: &mut dyn Write) -> std::io::Result<()> {
+        match self {
+            Event::Start { operation } => writeln!(out, "{}...", operation.verb())?,
+            Event::Finish {
+                operation,
+                freed: Some(freed),
+                dry_run,
+            } => writeln!(
+                out,
+                "{} {}, {} {}.",
+                operation.verb(),
+                "finished".bright_green(),
+                crate::fs::human_size(*freed),
+                if *dry_run { "would be freed" } else { "freed" }
+            )?,
+            Event::Finish { operation, .. } => {
+                writeln!(out, "{} {}.", operation.verb(), "finished".bright_green())?
+            }
+            Event::Project {
+                project,
+                language,
+                build_tool,
+                profile,
+            } => writeln!(
+                out,
+                "project '{}' ({}, {}, profile '{}'):",
+                project, language, build_tool, profile
+            )?,
+            Event::Fresh { .. } => writeln!(out, "    fresh.")?,
+            Event::Dirty { reasons, .. } => {
+                for reason in reasons {
+                    writeln!(out, "    dirty: {}.", reason)?;
+                }
+            }
+            Event::Output {
+                stream: Stream::Stdout,
+                line,
+                project,
+                ..
+            } => match project {
+                Some(_) => writeln!(out, "    {}", line)?,
+                None => writeln!(out, "{}", line)?,
+            },
+            Event::Output {
+                stream: Stream::Stderr,
+                line,
+                ..
+            } => writeln!(err, "{}", line)?,
+            // the rendered diagnostics of the compilers
+            Event::Diagnostic {
+                project: Some(_),
+                message,
+                ..
+            } => write!(err, "{}", message)?,
+            Event::Diagnostic {
+                project: None,
+                level,
+                message,
+                hint,
+            } => {
+                let text = match level {
+                    Level::Info => format!("{}: {}", "INFO".bright_white(), message),
+                    Level::Warning => format!("{}: {}", "WARNING".bright_yellow(), message),
+                    Level::Error => format!("{}: {}", "ERROR".bright_red(), message),
+                };
+                match level {
+                    Level::Error => writeln!(err, "{}", text)?,
+                    _ => writeln!(out, "{}", text)?,
+                }
+                if let Some(hint) = hint {
+                    writeln!(err, "{}: {}", "HINT".bright_cyan(), hint)?;
+                }
+            }
+            Event::Artifact { path, .. } => writeln!(out, "    artifact '{}'", path.display())?,
+            Event::Removed {
+                path,
+                files,
+                bytes,
+                dry_run,
+            } => writeln!(
+                out,
+                "    {} '{}' ({} files, {}).",
+                if *dry_run { "would remove" } else { "removed" },
+                path.display(),
+                files,
+                crate::fs::human_size(*bytes)
+            )?,
+            Event::Exited {
+                code: Some(code), ..
+            } => writeln!(out, "the program is exited with code '{}'.", code)?,
+            Event::Exited {
+                timeout_ms: Some(timeout_ms),
+                ..
+            } => writeln!(
+                out,
+                "{}: the program was killed after {:?}.",
+                "WARNING".bright_yellow(),
+                Duration::from_millis(*timeout_ms)
+            )?,
+            Event::Exited { .. } => writeln!(out, "the program is terminated by a signal.")?,
+            Event::Value {
+                value: toml::Value::String(value),
+                ..
+            } => writeln!(out, "{}", value)?,
+            Event::Value { value, .. } => writeln!(out, "{}", value)?,
+            Event::Setting {
+                key,
+                value,
+                origin: Some(origin),
+            } => writeln!(out, "{} = {} ({})", key, value, origin)?,
+            Event::Setting { key, value, .. } => writeln!(out, "{} = {}", key, value)?,
+            Event::Message {
+                project: Some(_),
+                message,
+            } => writeln!(out, "    {}", message)?,
+            Event::Message {
+                project: None,
+                message,
+            } => writeln!(out, "{}", message)?,
+            Event::Command { command, .. } => writeln!(out, "    running `{}`", command)?,
+            Event::Exit { .. } => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(event: &Event) -> String {
+        serde_json::to_string(&Versioned {
+            version: SCHEMA_VERSION,
+            event,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn json_events() {
+        assert_eq!(
+            json(&Event::Start {
+                operation: Operation::Build
+            }),
+            r#"{"version":1,"event":"start","operation":"build"}"#
+        );
+        assert_eq!(
+            json(&Event::Finish {
+                operation: Operation::Clean,
+                freed: Some(1024),
+                dry_run: true,
+            }),
+            r#"{"version":1,"event":"finish","operation":"clean","freed":1024,"dry-run":true}"#
+        );
+        assert_eq!(
+            json(&Event::Project {
+                project: "foo".to_owned(),
+                language: Language::Cpp,
+                build_tool: BuildTool::CMake,
+                profile: "debug".to_owned(),
+            }),
+            concat!(
+                r#"{"version":1,"event":"project","project":"foo","language":"cpp","#,
+                r#""build-tool":"cmake","profile":"debug"}"#
+            )
+        );
+        assert_eq!(
+            json(&Event::Exited {
+                binary: "foo".to_owned(),
+                code: None,
+                signal: None,
+                timeout_ms: Some(500),
+            }),
+            r#"{"version":1,"event":"exited","binary":"foo","timeout-ms":500}"#
+        );
+        assert_eq!(
+            json(&Event::Value {
+                key: "config.members".to_owned(),
+                value: toml::Value::Array(vec!["foo".into()]),
+            }),
+            r#"{"version":1,"event":"value","key":"config.members","value":["foo"]}"#
+        );
+    }
+
+    /// the text of the event on stdout and stderr, without colors
+    fn text(event: &Event) -> (String, String) {
+        colored::control::set_override(false);
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        event.write(&mut out, &mut err).unwrap();
+        (
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    #[test]
+    fn verbosity_of_events() {
+        assert!(
+            Verbosity::Quiet < Verbosity::Normal && Verbosity::Verbose < Verbosity::VeryVerbose
+        );
+        let warning = Event::Diagnostic {
+            project: None,
+            level: Level::Warning,
+            message: "warning".to_owned(),
+            hint: None,
+        };
+        assert_eq!(warning.verbosity(), Verbosity::Quiet);
+        let output = |stream| Event::Output {
+            project: Some("foo".to_owned()),
+            binary: None,
+            stream,
+            line: "line".to_owned(),
+        };
+        assert_eq!(output(Stream::Stdout).verbosity(), Verbosity::Normal);
+        assert_eq!(output(Stream::Stderr).verbosity(), Verbosity::Quiet);
+        let command = Event::Command {
+            project: "foo".to_owned(),
+            command: "make".to_owned(),
+        };
+        assert_eq!(command.verbosity(), Verbosity::Verbose);
+        assert_eq!(Event::Exit { code: 0 }.verbosity(), Verbosity::Quiet);
+    }
+
+    #[test]
+    fn human_text() {
+        let error = Event::Diagnostic {
+            project: None,
+            level: Level::Error,
+            message: "failed".to_owned(),
+            hint: Some("retry".to_owned()),
+        };
+        assert_eq!(
+            text(&error),
+            (String::new(), "ERROR: failed\nHINT: retry\n".to_owned())
+        );
+        let dirty = Event::Dirty {
+            project: "foo".to_owned(),
+            reasons: vec!["file 'a.c' changed".to_owned()],
+        };
+        assert_eq!(
+            text(&dirty),
+            ("    dirty: file 'a.c' changed.\n".to_owned(), String::new())
+        );
+        let finish = Event::Finish {
+            operation: Operation::Gc,
+            freed: Some(2048),
+            dry_run: false,
+        };
+        assert_eq!(
+            text(&finish).0,
+            "collecting garbage finished, 2.0 KiB freed.\n"
+        );
+    }
+}
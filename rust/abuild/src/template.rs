@@ -0,0 +1,389 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! project templates
+//!
+//! > built-in templates:
+//! >
+//! > | language | build tool | files                                 |
+//! > |----------|------------|---------------------------------------|
+//! > | rust     | cargo      | `Cargo.toml`, `src/main.rs`           |
+//! > | c        | make       | `Makefile`, `src/main.c`              |
+//! > | c        | cmake      | `CMakeLists.txt`, `src/main.c`        |
+//! > | cpp      | make       | `Makefile`, `src/main.cpp`            |
+//! > | cpp      | cmake      | `CMakeLists.txt`, `src/main.cpp`      |
+//! > | csharp   | make       | `Makefile`, `src/Program.cs`          |
+//! > | csharp   | msbuild    | `<project>.csproj`, `src/Program.cs`  |
+//!
+//! > user templates:
+//! >
+//! > every directory in `<user config dir>/templates` (e.g. `~/.config/abuild/templates/<name>`)
+//! > is a template, which can be used by `abuild create -j <project> --template <name>`.
+//! > a `.abuild/config.toml` in the template is used as the config of the project.
+//!
+//! > variables:
+//! >
+//! > - `project_name`: the name of the project
+//! > - `workspace_name`: the name of the workspace
+//! > - `author`: the first author of the workspace, or the current user
+//! > - `year`: the current year
+//!
+//! + ![note] `{{name}}` in file names and contents is replaced by the variable `name`.
+//! + ![note] files that are not valid UTF-8 are copied as is.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::lang::{BuildTool, Language};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// the variables substituted in templates
+pub type Variables = BTreeMap<&'static str, String>;
+
+/// a set of files (relative path and content) to generate a project from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    files: Vec<(Cow<'static, str>, Cow<'static, [u8]>)>,
+}
+
+impl Template {
+    /// the built-in template for the language and build tool
+    pub fn builtin(language: Language, build_tool: BuildTool) -> Option<Self> {
+        let files: &[(&'static str, &'static str)] = match (language, build_tool) {
+            (Language::Rust, BuildTool::Cargo) => {
+                &[("Cargo.toml", RUST_CARGO_TOML), ("src/main.rs", RUST_MAIN)]
+            }
+            (Language::C, BuildTool::Make) => &[("Makefile", C_MAKEFILE), ("src/main.c", C_MAIN)],
+            (Language::C, BuildTool::CMake) => {
+                &[("CMakeLists.txt", C_CMAKE_LISTS), ("src/main.c", C_MAIN)]
+            }
+            (Language::Cpp, BuildTool::Make) => {
+                &[("Makefile", CPP_MAKEFILE), ("src/main.cpp", CPP_MAIN)]
+            }
+            (Language::Cpp, BuildTool::CMake) => &[
+                ("CMakeLists.txt", CPP_CMAKE_LISTS),
+                ("src/main.cpp", CPP_MAIN),
+            ],
+            (Language::CSharp, BuildTool::Make) => &[
+                ("Makefile", CSHARP_MAKEFILE),
+                ("src/Program.cs", CSHARP_MAIN),
+            ],
+            (Language::CSharp, BuildTool::MSBuild) => &[
+                ("{{project_name}}.csproj", CSHARP_CSPROJ),
+                ("src/Program.cs", CSHARP_MAIN),
+            ],
+            _ => return None,
+        };
+        Some(Self {
+            files: files
+                .iter()
+                .map(|&(path, content)| (Cow::Borrowed(path), Cow::Borrowed(content.as_bytes())))
+                .collect(),
+        })
+    }
+    /// load the template from all files in `dir`
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        fn load_dir(
+            root: &Path,
+            dir: &Path,
+            files: &mut Vec<(Cow<'static, str>, Cow<'static, [u8]>)>,
+        ) -> io::Result<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    load_dir(root, &path, files)?;
+                } else {
+                    let relative = path
+                        .strip_prefix(root)
+                        .map_err(io::Error::other)?
+                        .components()
+                        .map(|component| component.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    files.push((Cow::Owned(relative), Cow::Owned(std::fs::read(&path)?)));
+                }
+            }
+            Ok(())
+        }
+        let mut files = Vec::new();
+        load_dir(dir, dir, &mut files)?;
+        files.sort();
+        Ok(Self { files })
+    }
+    /// the user template named `name`
+    pub fn user(name: &str) -> Option<io::Result<Self>> {
+        let dir = user_templates_dir()?.join(name);
+        Some(Self::load(&dir))
+    }
+    /// the files of the template
+    pub fn files(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.files
+            .iter()
+            .map(|(path, content)| (path.as_ref(), content.as_ref()))
+    }
+    /// generate the files into `dir` with the variables substituted
+    pub fn render(&self, dir: &Path, variables: &Variables) -> io::Result<()> {
+        for (path, content) in self.files() {
+            let path = dir.join(substitute(path, variables));
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            match std::str::from_utf8(content) {
+                Ok(content) => std::fs::write(path, substitute(content, variables))?,
+                Err(_) => std::fs::write(path, content)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the directory of user templates (`<user config dir>/templates`)
+pub fn user_templates_dir() -> Option<PathBuf> {
+    Some(crate::user_config_dir()?.join("templates"))
+}
+
+/// the default variables for the project `project_name` in the workspace `workspace_name`
+pub fn variables(project_name: &str, workspace_name: &str, authors: &[String]) -> Variables {
+    let mut variables = Variables::new();
+    variables.insert("project_name", project_name.to_owned());
+    variables.insert("workspace_name", workspace_name.to_owned());
+    variables.insert(
+        "author",
+        authors
+            .first()
+            .cloned()
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .unwrap_or_default(),
+    );
+    variables.insert("year", current_year().to_string());
+    variables
+}
+
+/// the current year (UTC)
+fn current_year() -> i64 {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86400) as i64;
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    yoe + era * 400 + if month <= 2 { 1 } else { 0 }
+}
+
+/// replace each `{{name}}` in `text` by the variable `name`, unknown variables are kept as is
+pub fn substitute(text: &str, variables: &Variables) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                match variables.get(after[..end].trim()) {
+                    Some(value) => result.push_str(value),
+                    None => result.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+const RUST_CARGO_TOML: &str = r#"[package]
+name = "{{project_name}}"
+version = "0.1.0"
+edition = "2024"
+
+[workspace]
+
+[dependencies]
+"#;
+
+const RUST_MAIN: &str = r#"fn main() {
+    println!("Hello, world!");
+}
+"#;
+
+const C_MAIN: &str = r#"#include <stdio.h>
+
+int main(void) {
+    printf("Hello, world!\n");
+    return 0;
+}
+"#;
+
+const C_MAKEFILE: &str = "CC ?= cc
+CFLAGS ?= -g
+OUT_DIR ?= build
+
+SRCS := $(wildcard src/*.c)
+
+all: $(OUT_DIR)/{{project_name}}
+
+$(OUT_DIR)/{{project_name}}: $(SRCS)
+\t@mkdir -p $(OUT_DIR)
+\t$(CC) $(CFLAGS) -o $@ $(SRCS)
+
+clean:
+\trm -rf $(OUT_DIR)
+
+.PHONY: all clean
+";
+
+const C_CMAKE_LISTS: &str = "cmake_minimum_required(VERSION 3.16)
+project({{project_name}} LANGUAGES C)
+
+add_executable({{project_name}} src/main.c)
+";
+
+const CPP_MAIN: &str = r#"#include <iostream>
+
+int main() {
+    std::cout << "Hello, world!" << std::endl;
+    return 0;
+}
+"#;
+
+const CPP_MAKEFILE: &str = "CXX ?= c++
+CXXFLAGS ?= -g
+OUT_DIR ?= build
+
+SRCS := $(wildcard src/*.cpp)
+
+all: $(OUT_DIR)/{{project_name}}
+
+$(OUT_DIR)/{{project_name}}: $(SRCS)
+\t@mkdir -p $(OUT_DIR)
+\t$(CXX) $(CXXFLAGS) -o $@ $(SRCS)
+
+clean:
+\trm -rf $(OUT_DIR)
+
+.PHONY: all clean
+";
+
+const CPP_CMAKE_LISTS: &str = "cmake_minimum_required(VERSION 3.16)
+project({{project_name}} LANGUAGES CXX)
+
+add_executable({{project_name}} src/main.cpp)
+";
+
+const CSHARP_MAIN: &str = r#"using System;
+
+class Program
+{
+    static void Main()
+    {
+        Console.WriteLine("Hello, world!");
+    }
+}
+"#;
+
+const CSHARP_MAKEFILE: &str = "CSC ?= mcs
+OUT_DIR ?= build
+
+SRCS := $(wildcard src/*.cs)
+
+all: $(OUT_DIR)/{{project_name}}.exe
+
+$(OUT_DIR)/{{project_name}}.exe: $(SRCS)
+\t@mkdir -p $(OUT_DIR)
+\t$(CSC) -out:$@ $(SRCS)
+
+clean:
+\trm -rf $(OUT_DIR)
+
+.PHONY: all clean
+";
+
+const CSHARP_CSPROJ: &str = r#"<Project Sdk="Microsoft.NET.Sdk">
+
+  <PropertyGroup>
+    <OutputType>Exe</OutputType>
+    <TargetFramework>net8.0</TargetFramework>
+    <AssemblyName>{{project_name}}</AssemblyName>
+    <Nullable>enable</Nullable>
+  </PropertyGroup>
+
+</Project>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Variables {
+        Variables::from([
+            ("project_name", "foo".to_owned()),
+            ("year", "2025".to_owned()),
+        ])
+    }
+
+    #[test]
+    fn substitute_variables() {
+        assert_eq!(
+            substitute("name = \"{{project_name}}\"", &variables()),
+            "name = \"foo\""
+        );
+        assert_eq!(substitute("{{ project_name }}", &variables()), "foo");
+        assert_eq!(
+            substitute("{{project_name}}-{{year}}", &variables()),
+            "foo-2025"
+        );
+        assert_eq!(substitute("no variables", &variables()), "no variables");
+    }
+
+    #[test]
+    fn substitute_keeps_unknown_and_unclosed() {
+        assert_eq!(
+            substitute("{{unknown}} {{year}}", &variables()),
+            "{{unknown}} 2025"
+        );
+        assert_eq!(
+            substitute("{{year}} {{project_name", &variables()),
+            "2025 {{project_name"
+        );
+        assert_eq!(substitute("}} {{", &variables()), "}} {{");
+    }
+
+    #[test]
+    fn builtin_templates_of_all_build_tools() {
+        use clap::ValueEnum;
+        for language in Language::value_variants() {
+            for &build_tool in language.build_tools() {
+                assert!(
+                    Template::builtin(*language, build_tool).is_some(),
+                    "no template for {} with {}",
+                    language,
+                    build_tool
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright (c) 2025 air (https://yuanair.github.io).
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, version 3 of the License only.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//!
+//! staging area of removed files
+//!
+//! > workspace:
+//! > > .abuild:
+//! > > > trash:
+//! > > > > <time>-<kind>-<name>:
+//! > > > > > ... # the removed files
+//! > > > >
+//! > > >
+//! > >
+//! >
+//!
+//! + ![note] files are never deleted by `abuild remove`, they are moved into the trash instead.
+//!
+//! [note]: https://img.shields.io/badge/note-orange.svg?color=ddbb00
+//!
+
+use crate::fs::DiskUsage;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const TRASH_DIR_NAME: &str = "trash";
+
+/// a file or directory moved into the trash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moved {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub usage: DiskUsage,
+}
+
+/// the trash directory of the workspace (`.abuild/trash`)
+pub fn trash_dir(workspace_dir: &Path) -> PathBuf {
+    crate::app_config_dir_in(workspace_dir).join(TRASH_DIR_NAME)
+}
+
+/// create a new, unique entry in the trash of the workspace
+pub fn new_entry(workspace_dir: &Path, kind: &str, name: &str) -> io::Result<PathBuf> {
+    let trash_dir = trash_dir(workspace_dir);
+    std::fs::create_dir_all(&trash_dir)?;
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let mut index = 0;
+    loop {
+        let entry = match index {
+            0 => trash_dir.join(format!("{}-{}-{}", time, kind, name)),
+            index => trash_dir.join(format!("{}-{}-{}.{}", time, kind, name, index)),
+        };
+        match std::fs::create_dir(&entry) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => index += 1,
+            res => return res.map(|_| entry),
+        }
+    }
+}
+
+/// move `from` into the trash entry `entry` as `name`
+pub fn stage(entry: &Path, from: &Path, name: impl AsRef<Path>) -> io::Result<Moved> {
+    let usage = DiskUsage::of(from)?;
+    let to = entry.join(name);
+    crate::fs::move_path(from, &to)?;
+    Ok(Moved {
+        from: from.to_path_buf(),
+        to,
+        usage,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_into_unique_entries() {
+        let workspace_dir =
+            std::env::temp_dir().join(format!("abuild-trash-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&workspace_dir);
+        let project_dir = workspace_dir.join("app");
+        std::fs::create_dir_all(project_dir.join("src")).unwrap();
+        std::fs::write(project_dir.join("src").join("main.c"), "int main;").unwrap();
+
+        let entry = new_entry(&workspace_dir, "project", "app").unwrap();
+        let other = new_entry(&workspace_dir, "project", "app").unwrap();
+        assert_ne!(entry, other);
+        assert!(entry.starts_with(trash_dir(&workspace_dir)));
+
+        let moved = stage(&entry, &project_dir, "app").unwrap();
+        assert_eq!(moved.from, project_dir);
+        assert_eq!(moved.to, entry.join("app"));
+        assert_eq!(moved.usage, DiskUsage { files: 1, bytes: 9 });
+        assert!(!project_dir.exists());
+        assert!(moved.to.join("src").join("main.c").is_file());
+        std::fs::remove_dir_all(&workspace_dir).unwrap();
+    }
+}